@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use iced::Command;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::bookmark::Bookmark;
+use crate::id::MovieId;
+use crate::message::Message;
+use crate::save;
+use crate::state::State;
+use crate::tmdb::RequestType;
+
+/// Deleted bookmark which can be restored.
+/// Its links, notes and watch history are kept until it is purged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedBookmark {
+    pub bookmark: Bookmark,
+    pub archived: DateTime<Utc>,
+}
+/// Action on the archive which needs to be confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveConfirmation {
+    Archive(MovieId),
+    Purge(MovieId),
+    PurgeAll,
+}
+impl State {
+    pub fn get_archived(&self, id: MovieId) -> Option<&ArchivedBookmark> {
+        self.archive
+            .iter()
+            .find(|archived| archived.bookmark.movie.id == id)
+    }
+    /// Asks for the confirmation of the action
+    pub fn confirmation_question(&self, confirmation: ArchiveConfirmation) -> String {
+        let name = |id| {
+            self.get_bookmark(id)
+                .or_else(|| Some(&self.get_archived(id)?.bookmark))
+                .map(|bookmark| bookmark.movie.name.clone())
+                .unwrap_or_default()
+        };
+        match confirmation {
+            ArchiveConfirmation::Archive(id) => {
+                format!("Delete {}? It can be restored from the archive", name(id))
+            }
+            ArchiveConfirmation::Purge(id) => {
+                format!("Permanently delete {} and its links?", name(id))
+            }
+            ArchiveConfirmation::PurgeAll => format!(
+                "Permanently delete all {} archived bookmarks and their links?",
+                self.archive.len()
+            ),
+        }
+    }
+    /// Moves the bookmark from the bookmarks to the archive
+    pub fn archive_bookmark(&mut self, id: MovieId) {
+        let Some(index) = self.bookmarks.iter().position(|b| b.movie.id == id) else {
+            warn!("Tried to archive bookmark {id}, but no such bookmark exists");
+            return;
+        };
+        info!("Archive bookmark {id}");
+        let bookmark = self.bookmarks.remove(index);
+        self.archive.push(ArchivedBookmark {
+            bookmark,
+            archived: Utc::now(),
+        });
+    }
+    /// Moves the bookmark from the archive back to the bookmarks and loads its details
+    /// and poster unless they are still cached. None if the bookmark is not archived
+    pub fn restore_bookmark(&mut self, id: MovieId) -> Option<Command<Message>> {
+        let index = self
+            .archive
+            .iter()
+            .position(|archived| archived.bookmark.movie.id == id)?;
+        info!("Restore bookmark {id}");
+        let bookmark = self.archive.remove(index).bookmark;
+        let mut requests = Vec::new();
+        if !self.movie_details.contains_key(&id) {
+            requests.push(RequestType::TvDetails { id });
+        }
+        if let Some(path) = bookmark
+            .movie
+            .poster_path
+            .clone()
+            .filter(|_| !self.movie_posters.contains_key(&id))
+        {
+            requests.push(RequestType::Poster { id, path });
+        }
+        self.bookmarks.push(bookmark);
+        Some(Command::batch(requests.into_iter().map(|request| {
+            Command::perform(async {}, move |_| Message::ExecuteRequest(request))
+        })))
+    }
+    /// Permanently removes the archived bookmarks together with everything belonging to them,
+    /// then removes links, notes and the watch history of bookmarks which don't exist anymore.
+    /// Cached data and posters of the purged bookmarks are kept while they are shown as search result
+    pub fn purge(&mut self, ids: &[MovieId]) -> Command<Message> {
+        info!("Purge {} archived bookmarks", ids.len());
+        self.archive
+            .retain(|archived| !ids.contains(&archived.bookmark.movie.id));
+        let known: HashSet<MovieId> = self
+            .bookmarks
+            .iter()
+            .chain(self.archive.iter().map(|archived| &archived.bookmark))
+            .map(|bookmark| bookmark.movie.id)
+            .collect();
+        let orphaned_links = self.links.len();
+        self.links.retain(|id, _| known.contains(id));
+        let orphaned_links = orphaned_links - self.links.len();
+        if orphaned_links > 0 {
+            info!("Removed the links of {orphaned_links} deleted bookmarks");
+        }
+        self.episode_notes
+            .retain(|episode, _| known.contains(&episode.0));
+        self.watch_log.retain(|event| known.contains(&event.id));
+        let unused: HashSet<MovieId> = ids
+            .iter()
+            .filter(|id| !known.contains(id) && !self.movies.iter().any(|movie| movie.id == **id))
+            .copied()
+            .collect();
+        self.movie_posters.retain(|id, _| !unused.contains(id));
+        self.movie_details.retain(|id, _| !unused.contains(id));
+        self.season_details.retain(|id, _| !unused.contains(id));
+        self.episode_details
+            .retain(|episode, _| !unused.contains(&episode.0));
+        self.details_refreshed.retain(|id, _| !unused.contains(id));
+        self.refresh_attempted.retain(|id, _| !unused.contains(id));
+        self.details_etags.retain(|id, _| !unused.contains(id));
+        self.libraries.retain(|id, _| !unused.contains(id));
+        Command::perform(save::remove_posters(unused), Message::PostersRemoved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::SyncMode;
+    use crate::link::BookmarkLinks;
+    use crate::message::BookmarkMessage;
+    use crate::movie::test_movie;
+    use crate::stats::WatchEvent;
+
+    fn state() -> State {
+        let movies: Vec<_> = (1..=3).map(|id| test_movie(id, "Show")).collect();
+        let mut state = State {
+            bookmarks: movies.iter().map(Bookmark::from).collect(),
+            ..State::default()
+        };
+        for bookmark in &state.bookmarks {
+            let id = bookmark.movie.id;
+            state.links.insert(id, BookmarkLinks::default());
+            state
+                .episode_notes
+                .insert(bookmark.current_episode_id(), "Note".to_owned());
+            state.watch_log.push(WatchEvent {
+                id,
+                episode: bookmark.current_episode.clone(),
+                time: Utc::now(),
+                runtime: None,
+            });
+            state.details_etags.insert(id, "etag".to_owned());
+        }
+        state.movies = movies;
+        state
+    }
+
+    #[test]
+    fn purge_removes_everything_of_the_bookmark() {
+        let mut state = state();
+        let ids: Vec<MovieId> = state.bookmarks.iter().map(|b| b.movie.id).collect();
+        state.archive_bookmark(ids[0]);
+        state.archive_bookmark(ids[1]);
+        // The first one is still a search result
+        state.movies.retain(|movie| movie.id == ids[0]);
+        let _ = state.purge(&ids[..2]);
+        assert!(state.archive.is_empty());
+        for data in [
+            state.links.keys().copied().collect::<Vec<_>>(),
+            state.episode_notes.keys().map(|id| id.0).collect(),
+            state.watch_log.iter().map(|event| event.id).collect(),
+        ] {
+            assert_eq!(data, [ids[2]]);
+        }
+        let mut cached: Vec<MovieId> = state.details_etags.keys().copied().collect();
+        cached.sort_by_key(MovieId::id);
+        assert_eq!(cached, [ids[0], ids[2]]);
+    }
+    #[test]
+    fn adding_an_archived_bookmark_restores_it() {
+        let mut state = state();
+        let id = state.bookmarks[0].movie.id;
+        state.bookmarks[0].sync_mode = SyncMode::NoSync;
+        let first = state.bookmarks[0].current_episode.clone();
+        let _ = state.bookmarks[0].apply(BookmarkMessage::IncrE(None));
+        let progress = state.bookmarks[0].current_episode.clone();
+        assert_ne!(progress, first);
+        state.archive_bookmark(id);
+        assert!(state.get_bookmark(id).is_none());
+        let _ = state.update_state(Message::AddBookmark(id));
+        assert!(state.archive.is_empty());
+        let restored = state.bookmarks.last().unwrap();
+        assert_eq!(restored.movie.id, id);
+        assert_eq!(restored.current_episode, progress);
+        assert!(state.links.contains_key(&id));
+        assert!(state.episode_notes.keys().any(|episode| episode.0 == id));
+        assert!(state.watch_log.iter().any(|event| event.id == id));
+    }
+}
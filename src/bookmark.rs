@@ -1,5 +1,8 @@
-use std::fmt::Debug;
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display};
+use std::path::PathBuf;
 
+use chrono::{DateTime, Utc};
 use iced::widget::image;
 use iced::Command;
 
@@ -9,7 +12,8 @@ use tracing::{debug, info, warn};
 use crate::id::EpisodeId;
 use crate::message::{BookmarkMessage, Message};
 use crate::movie::TmdbMovie;
-use crate::movie_details::{Episode, TotalEpisode};
+use crate::movie_details::{Episode, MovieDetails, SeasonEpisode, TotalEpisode};
+use crate::stats::Progress;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bookmark {
@@ -17,10 +21,76 @@ pub struct Bookmark {
     pub current_episode: Episode,
     /// True if the current episode is already watched
     pub finished: bool,
+    /// Lifecycle status of the bookmark.
+    /// Older states don't have a status, in which case it is derived from `finished`
+    #[serde(default)]
+    status: Option<WatchStatus>,
     pub sync_mode: SyncMode,
+    /// Progress of an ongoing rewatch.
+    /// The completion record in `current_episode` stays untouched while rewatching
+    #[serde(default)]
+    pub rewatch: Option<Episode>,
+    /// How often the bookmark was rewatched completely
+    #[serde(default)]
+    pub rewatch_count: usize,
+    /// The latest published episode known from the previous details request.
+    /// Used to detect episodes which were published in the meantime
+    #[serde(default)]
+    pub last_known_episode: Option<SeasonEpisode>,
+    /// True if no notifications should be shown for new episodes
+    #[serde(default)]
+    pub muted: bool,
+    /// Directory containing the episodes on disk
+    #[serde(default)]
+    pub local_dir: Option<PathBuf>,
+    /// Time at which the bookmark was created. Unknown for older bookmarks
+    #[serde(default)]
+    pub added: Option<DateTime<Utc>>,
+    /// Time at which the progress was advanced the last time
+    #[serde(default)]
+    pub last_watched: Option<DateTime<Utc>>,
+    /// User defined labels like who the show is watched with
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// Personal rating from 1 to 10
+    #[serde(default)]
+    pub rating: Option<u8>,
+    #[serde(default)]
+    pub notes: String,
+}
+/// Lifecycle of a bookmark from planning to completion
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WatchStatus {
+    PlanToWatch,
+    #[default]
+    Watching,
+    OnHold,
+    Dropped,
+    Completed,
+}
+impl WatchStatus {
+    pub const ALL: [WatchStatus; 5] = [
+        WatchStatus::PlanToWatch,
+        WatchStatus::Watching,
+        WatchStatus::OnHold,
+        WatchStatus::Dropped,
+        WatchStatus::Completed,
+    ];
+}
+impl Display for WatchStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            WatchStatus::PlanToWatch => "Plan to watch",
+            WatchStatus::Watching => "Watching",
+            WatchStatus::OnHold => "On hold",
+            WatchStatus::Dropped => "Dropped",
+            WatchStatus::Completed => "Completed",
+        };
+        write!(f, "{name}")
+    }
 }
 /// Defines how the bookmark progress should be handled
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SyncMode {
     /// The progress is not synced with the Tmdb database, allowing the bookmark to have a episode that is not registered in the database.
     /// Also the season will never change unless explicitly incremented
@@ -40,29 +110,56 @@ impl From<&TmdbMovie> for Bookmark {
             movie: movie.clone(),
             current_episode: Episode::Total(TotalEpisode { episode: 1 }),
             finished: false,
+            status: Some(WatchStatus::Watching),
             sync_mode: SyncMode::Tmdb,
+            rewatch: None,
+            rewatch_count: 0,
+            last_known_episode: None,
+            muted: false,
+            local_dir: None,
+            added: Some(Utc::now()),
+            last_watched: None,
+            tags: BTreeSet::new(),
+            rating: None,
+            notes: String::new(),
         }
     }
 }
 impl Bookmark {
     pub fn apply(&mut self, action: BookmarkMessage) -> Command<Message> {
+        if let BookmarkMessage::IncrE(_) | BookmarkMessage::FinishSeason(_) = action {
+            self.last_watched = Some(Utc::now());
+        }
+        if self.rewatch.is_some() {
+            if let BookmarkMessage::IncrE(_)
+            | BookmarkMessage::DecrE(_)
+            | BookmarkMessage::FinishSeason(_) = action
+            {
+                return self.apply_rewatch(action);
+            }
+        }
         match action {
-            BookmarkMessage::IncrE(details) => match self.sync_mode {
-                SyncMode::NoSync => self.current_episode.next_episode(),
-                SyncMode::Tmdb => {
-                    if let Some(details) = details {
-                        debug!("increment bookmark episode {:?}", self);
-                        let next_episode = details.next_episode(self.current_episode.clone());
-                        self.finished = next_episode == self.current_episode;
-                        self.current_episode = next_episode;
-                    } else {
-                        warn!(
+            BookmarkMessage::IncrE(details) => {
+                if self.status() == WatchStatus::PlanToWatch {
+                    self.set_status(WatchStatus::Watching);
+                }
+                match self.sync_mode {
+                    SyncMode::NoSync => self.current_episode.next_episode(),
+                    SyncMode::Tmdb => {
+                        if let Some(details) = details {
+                            debug!("increment bookmark episode {:?}", self);
+                            let next_episode = details.next_episode(self.current_episode.clone());
+                            self.set_finished(next_episode == self.current_episode);
+                            self.current_episode = next_episode;
+                        } else {
+                            warn!(
                             "Can not increment episode as the movie details are not loaded id: {}",
                             self.movie.id
                         );
+                        }
                     }
                 }
-            },
+            }
             BookmarkMessage::DecrE(details) => {
                 match self.sync_mode {
                     SyncMode::NoSync => self.current_episode.previous_episode(),
@@ -71,12 +168,12 @@ impl Bookmark {
                             debug!("decrement bookmark episode {:?}", self);
                             if self.finished {
                                 debug!("Since the bookmark was on finished state, finish flag was removed");
-                                self.finished = false;
+                                self.set_finished(false);
                             } else {
                                 let previous_episode =
                                     details.previous_episode(self.current_episode.clone());
                                 self.current_episode = previous_episode;
-                                self.finished = false;
+                                self.set_finished(false);
                             }
                         } else {
                             warn!(
@@ -92,7 +189,7 @@ impl Bookmark {
                     warn!("Tried to parse {episode} as a episode when only numbers are allowed");
                     return Command::none();
                 };
-                self.finished = false;
+                self.set_finished(false);
                 match self.sync_mode {
                     SyncMode::NoSync => self.current_episode.set_episode(episode),
                     SyncMode::Tmdb => {
@@ -137,7 +234,7 @@ impl Bookmark {
                     return Command::none();
                 };
 
-                self.finished = false;
+                self.set_finished(false);
                 match self.sync_mode {
                     SyncMode::NoSync => self.current_episode.set_season(new_season),
                     SyncMode::Tmdb => {
@@ -177,6 +274,80 @@ impl Bookmark {
                     }
                 }
             }
+            BookmarkMessage::FinishSeason(details) => {
+                if self.status() == WatchStatus::PlanToWatch {
+                    self.set_status(WatchStatus::Watching);
+                }
+                match self.sync_mode {
+                    SyncMode::NoSync => match &mut self.current_episode {
+                        Episode::Seasonal(episode) => {
+                            episode.season_number += 1;
+                            episode.episode_number = 1;
+                            self.set_finished(false);
+                        }
+                        Episode::Total(_) => warn!(
+                            "Can not finish the season of bookmark {} as it counts episodes without seasons",
+                            self.movie.id
+                        ),
+                    },
+                    SyncMode::Tmdb => {
+                        let Some(details) = details else {
+                            warn!(
+                                "Can not finish the season as the movie details are not loaded. id: {}",
+                                self.movie.id
+                            );
+                            return Command::none();
+                        };
+                        if let Some(start) = details.next_season_start(&self.current_episode) {
+                            debug!("finish season of bookmark {}", self.movie.id);
+                            self.current_episode = details.in_format_of(start, &self.current_episode);
+                            self.set_finished(false);
+                        } else if let Some(last_published) = details.last_published() {
+                            info!(
+                                "Finished the latest season of bookmark {}, set to the last published episode",
+                                self.movie.id
+                            );
+                            self.current_episode =
+                                details.in_format_of(last_published.episode, &self.current_episode);
+                            self.set_finished(true);
+                        }
+                    }
+                }
+            }
+            BookmarkMessage::JumpToLatest(details) => {
+                if self.sync_mode == SyncMode::NoSync {
+                    warn!(
+                        "Can not jump to the latest episode of bookmark {} as it is not synced with TMDB",
+                        self.movie.id
+                    );
+                    return Command::none();
+                }
+                let Some(details) = details else {
+                    warn!(
+                        "Can not jump to the latest episode as the movie details are not loaded. id: {}",
+                        self.movie.id
+                    );
+                    return Command::none();
+                };
+                let Some(last_published) = details.last_published() else {
+                    warn!("Bookmark {} has no published episode yet", self.movie.id);
+                    return Command::none();
+                };
+                if self.finished {
+                    debug!(
+                        "bookmark {} already watched the latest episode",
+                        self.movie.id
+                    );
+                    return Command::none();
+                }
+                info!("Jump to the latest episode of bookmark {}", self.movie.id);
+                self.current_episode =
+                    details.in_format_of(last_published.episode, &self.current_episode);
+                self.set_finished(false);
+                if self.status() == WatchStatus::PlanToWatch {
+                    self.set_status(WatchStatus::Watching);
+                }
+            }
             BookmarkMessage::ToggleSync => {
                 match self.sync_mode {
                     SyncMode::NoSync => self.sync_mode = SyncMode::Tmdb,
@@ -184,10 +355,322 @@ impl Bookmark {
                 }
                 info!("Toggle sync mode of bookmark with id {}", self.movie.id);
             }
+            BookmarkMessage::StartRewatch => {
+                if !self.finished {
+                    warn!(
+                        "Tried to rewatch bookmark {} which is not finished yet",
+                        self.movie.id
+                    );
+                    return Command::none();
+                }
+                let first_episode = match self.current_episode {
+                    Episode::Seasonal(_) => Episode::Seasonal(SeasonEpisode {
+                        episode_number: 1,
+                        season_number: 1,
+                    }),
+                    Episode::Total(_) => Episode::Total(TotalEpisode { episode: 1 }),
+                };
+                info!("Start rewatch of bookmark with id {}", self.movie.id);
+                self.rewatch = Some(first_episode);
+            }
+            BookmarkMessage::StopRewatch => {
+                info!("Stop rewatch of bookmark with id {}", self.movie.id);
+                self.rewatch = None;
+            }
+            BookmarkMessage::ToggleMute => {
+                self.muted = !self.muted;
+                info!(
+                    "Toggle notifications of bookmark {} to muted: {}",
+                    self.movie.id, self.muted
+                );
+            }
+            BookmarkMessage::SetStatus(status) => {
+                info!("Set status of bookmark {} to {status}", self.movie.id);
+                self.set_status(status);
+            }
+            BookmarkMessage::AddTag(tag) => {
+                self.tags.insert(tag);
+            }
+            BookmarkMessage::RemoveTag(tag) => {
+                self.tags.remove(&tag);
+            }
+            BookmarkMessage::SetRating(rating) => {
+                self.rating = rating.filter(|rating| (1..=10).contains(rating));
+            }
         }
         Command::none()
     }
+    /// Moves the rewatch progress instead of the completion record.
+    /// The rewatch is finished as soon as it catches up with `current_episode`
+    fn apply_rewatch(&mut self, action: BookmarkMessage) -> Command<Message> {
+        let Some(rewatch) = self.rewatch.clone() else {
+            return Command::none();
+        };
+        match action {
+            BookmarkMessage::IncrE(details) => {
+                let next = match (self.sync_mode, details) {
+                    (SyncMode::NoSync, _) => {
+                        let mut next = rewatch.clone();
+                        next.next_episode();
+                        next
+                    }
+                    (SyncMode::Tmdb, Some(details)) => details.next_episode(rewatch.clone()),
+                    (SyncMode::Tmdb, None) => {
+                        warn!(
+                            "Can not increment rewatch as the movie details are not loaded id: {}",
+                            self.movie.id
+                        );
+                        return Command::none();
+                    }
+                };
+                let caught_up = if self.finished {
+                    rewatch == self.current_episode
+                } else {
+                    next == self.current_episode
+                };
+                if caught_up || next == rewatch {
+                    self.finish_rewatch();
+                } else {
+                    debug!("increment rewatch episode {:?}", self);
+                    self.rewatch = Some(next);
+                }
+            }
+            BookmarkMessage::DecrE(details) => {
+                let previous = match (self.sync_mode, details) {
+                    (SyncMode::NoSync, _) => {
+                        let mut previous = rewatch;
+                        previous.previous_episode();
+                        previous
+                    }
+                    (SyncMode::Tmdb, Some(details)) => details.previous_episode(rewatch),
+                    (SyncMode::Tmdb, None) => {
+                        warn!(
+                            "Can not decrement rewatch as the movie details are not loaded id: {}",
+                            self.movie.id
+                        );
+                        return Command::none();
+                    }
+                };
+                self.rewatch = Some(previous);
+            }
+            BookmarkMessage::FinishSeason(details) => {
+                let next = match (self.sync_mode, &details) {
+                    (SyncMode::NoSync, _) => match rewatch {
+                        Episode::Seasonal(episode) => Some(Episode::Seasonal(SeasonEpisode {
+                            episode_number: 1,
+                            season_number: episode.season_number + 1,
+                        })),
+                        Episode::Total(_) => None,
+                    },
+                    (SyncMode::Tmdb, Some(details)) => details
+                        .next_season_start(&rewatch)
+                        .map(|start| details.in_format_of(start, &rewatch)),
+                    (SyncMode::Tmdb, None) => {
+                        warn!(
+                            "Can not finish the season of the rewatch as the movie details are not loaded id: {}",
+                            self.movie.id
+                        );
+                        return Command::none();
+                    }
+                };
+                // Positions as (season, episode) or total episode to find out whether the rewatch caught up
+                let position = |episode: &Episode| match &details {
+                    Some(details) => (0, details.as_total_episodes(episode).episode),
+                    None => (episode.season(), episode.episode()),
+                };
+                let caught_up = |next: &Episode| {
+                    let (next, current) = (position(next), position(&self.current_episode));
+                    next > current || (next == current && !self.finished)
+                };
+                match next {
+                    Some(next) if !caught_up(&next) => {
+                        debug!("finish season of rewatch {:?}", self);
+                        self.rewatch = Some(next);
+                    }
+                    _ => self.finish_rewatch(),
+                }
+            }
+            _ => warn!("{action:?} can not be applied to a rewatch"),
+        }
+        Command::none()
+    }
+    fn finish_rewatch(&mut self) {
+        self.rewatch_count += 1;
+        self.rewatch = None;
+        info!(
+            "Finished rewatch {} of bookmark with id {}",
+            self.rewatch_count, self.movie.id
+        );
+    }
+    pub fn status(&self) -> WatchStatus {
+        self.status.unwrap_or(if self.finished {
+            WatchStatus::Completed
+        } else {
+            WatchStatus::Watching
+        })
+    }
+    /// Sets the status and the finished flag with it.
+    /// Only completed bookmarks have their current episode watched
+    pub fn set_status(&mut self, status: WatchStatus) {
+        self.status = Some(status);
+        self.finished = status == WatchStatus::Completed;
+    }
+    /// Sets the finished flag and moves the status along.
+    /// Finishing completes the bookmark while unfinishing a completed bookmark resumes watching
+    pub(crate) fn set_finished(&mut self, finished: bool) {
+        self.finished = finished;
+        if finished {
+            self.set_status(WatchStatus::Completed);
+        } else if self.status() == WatchStatus::Completed {
+            self.set_status(WatchStatus::Watching);
+        }
+    }
+    /// Number of published episodes which are not watched yet
+    pub fn episodes_behind(&self, details: &MovieDetails) -> usize {
+        details.episodes_behind(&self.current_episode, self.finished)
+    }
+    pub fn is_rewatching(&self) -> bool {
+        self.rewatch.is_some()
+    }
+    /// The episode which is watched next.
+    /// While rewatching this is the rewatch progress instead of `current_episode`
+    pub fn active_episode(&self) -> &Episode {
+        self.rewatch.as_ref().unwrap_or(&self.current_episode)
+    }
     pub fn current_episode_id(&self) -> EpisodeId {
-        EpisodeId(self.movie.id, self.current_episode.clone())
+        EpisodeId(self.movie.id, self.active_episode().clone())
+    }
+    /// The active episode and whether it is watched, a rewatched episode never is
+    pub fn progress(&self) -> Progress {
+        Progress {
+            episode: self.current_episode_id(),
+            finished: self.finished && self.rewatch.is_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movie::test_movie;
+    use crate::movie_details::test_details;
+
+    fn seasonal(season_number: usize, episode_number: usize) -> Episode {
+        Episode::Seasonal(SeasonEpisode {
+            episode_number,
+            season_number,
+        })
+    }
+    /// A bookmark at the given episode of a show with seasons of 10, 8 and 6 episodes,
+    /// of which the third season aired up to its second episode
+    fn bookmark_at(episode: Episode, finished: bool) -> (Bookmark, MovieDetails) {
+        let mut bookmark = Bookmark::from(&test_movie(1, "Show"));
+        bookmark.current_episode = episode;
+        bookmark.set_finished(finished);
+        (bookmark, test_details(1, &[10, 8, 6], (3, 2), None))
+    }
+
+    #[test]
+    fn status_and_finished_stay_in_sync() {
+        let mut bookmark = Bookmark::from(&test_movie(1, "Show"));
+        let _ = bookmark.apply(BookmarkMessage::SetStatus(WatchStatus::Completed));
+        assert!(bookmark.finished);
+        let _ = bookmark.apply(BookmarkMessage::SetStatus(WatchStatus::Watching));
+        assert!(!bookmark.finished);
+        assert_eq!(bookmark.status(), WatchStatus::Watching);
+        bookmark.set_finished(true);
+        assert_eq!(bookmark.status(), WatchStatus::Completed);
+        let _ = bookmark.apply(BookmarkMessage::SetStatus(WatchStatus::OnHold));
+        assert!(!bookmark.finished);
+        bookmark.set_finished(false);
+        assert_eq!(bookmark.status(), WatchStatus::OnHold);
+    }
+
+    #[test]
+    fn finishing_a_season_starts_the_next_one() {
+        let (mut bookmark, details) = bookmark_at(seasonal(1, 4), false);
+        bookmark.set_status(WatchStatus::PlanToWatch);
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(Some(details.clone())));
+        assert_eq!(bookmark.current_episode, seasonal(2, 1));
+        assert!(!bookmark.finished);
+        assert_eq!(bookmark.status(), WatchStatus::Watching);
+        // Total episode counts stay in their format
+        let (mut bookmark, _) = bookmark_at(Episode::Total(TotalEpisode { episode: 4 }), false);
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(Some(details)));
+        assert_eq!(
+            bookmark.current_episode,
+            Episode::Total(TotalEpisode { episode: 11 })
+        );
+        // Nothing happens without details
+        let (mut bookmark, _) = bookmark_at(seasonal(1, 4), false);
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(None));
+        assert_eq!(bookmark.current_episode, seasonal(1, 4));
+    }
+
+    #[test]
+    fn finishing_the_latest_season_finishes_the_bookmark() {
+        let (mut bookmark, details) = bookmark_at(seasonal(3, 1), false);
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(Some(details)));
+        assert_eq!(bookmark.current_episode, seasonal(3, 2));
+        assert!(bookmark.finished);
+        assert_eq!(bookmark.status(), WatchStatus::Completed);
+    }
+
+    #[test]
+    fn finishes_seasons_without_sync() {
+        let (mut bookmark, _) = bookmark_at(seasonal(3, 2), true);
+        bookmark.sync_mode = SyncMode::NoSync;
+        // Seasons are counted up blindly
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(None));
+        assert_eq!(bookmark.current_episode, seasonal(4, 1));
+        assert!(!bookmark.finished);
+        assert_eq!(bookmark.status(), WatchStatus::Watching);
+        // Without seasons there is nothing to finish
+        let (mut bookmark, _) = bookmark_at(Episode::Total(TotalEpisode { episode: 4 }), false);
+        bookmark.sync_mode = SyncMode::NoSync;
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(None));
+        assert_eq!(
+            bookmark.current_episode,
+            Episode::Total(TotalEpisode { episode: 4 })
+        );
+    }
+
+    #[test]
+    fn finishing_rewatched_seasons_keeps_the_progress() {
+        let (mut bookmark, details) = bookmark_at(seasonal(3, 2), true);
+        let _ = bookmark.apply(BookmarkMessage::StartRewatch);
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(Some(details.clone())));
+        assert_eq!(bookmark.rewatch, Some(seasonal(2, 1)));
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(Some(details.clone())));
+        assert_eq!(bookmark.rewatch, Some(seasonal(3, 1)));
+        // There is no next season, so the rewatch is done
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(Some(details)));
+        assert_eq!(bookmark.rewatch, None);
+        assert_eq!(bookmark.rewatch_count, 1);
+        assert_eq!(bookmark.current_episode, seasonal(3, 2));
+        assert!(bookmark.finished);
+    }
+
+    #[test]
+    fn rewatches_end_when_catching_up() {
+        // Dropped after the third episode of the second season
+        let (mut bookmark, details) = bookmark_at(seasonal(2, 3), true);
+        let _ = bookmark.apply(BookmarkMessage::StartRewatch);
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(Some(details.clone())));
+        assert_eq!(bookmark.rewatch, Some(seasonal(2, 1)));
+        // The next season would skip past the progress
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(Some(details)));
+        assert_eq!(bookmark.rewatch, None);
+        assert_eq!(bookmark.rewatch_count, 1);
+        assert_eq!(bookmark.current_episode, seasonal(2, 3));
+        // Without sync the seasons and episodes are compared
+        let (mut bookmark, _) = bookmark_at(seasonal(2, 5), true);
+        bookmark.sync_mode = SyncMode::NoSync;
+        let _ = bookmark.apply(BookmarkMessage::StartRewatch);
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(None));
+        assert_eq!(bookmark.rewatch, Some(seasonal(2, 1)));
+        let _ = bookmark.apply(BookmarkMessage::FinishSeason(None));
+        assert_eq!(bookmark.rewatch, None);
+        assert_eq!(bookmark.current_episode, seasonal(2, 5));
     }
 }
@@ -0,0 +1,280 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{Datelike, Days, Local, NaiveDate};
+use iced::Command;
+
+use crate::bookmark::{Bookmark, WatchStatus};
+use crate::ics;
+use crate::message::Message;
+use crate::movie_details::EpisodeDetails;
+use crate::save;
+use crate::state::State;
+
+/// Time to wait after writing the calendar subscription before it is written again
+const CALENDAR_WRITE_DELAY: Duration = Duration::from_secs(2);
+
+/// Time span covered by the calendar view
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CalendarRange {
+    /// The next seven days grouped by day
+    #[default]
+    Week,
+    /// The next month grouped by week
+    Month,
+}
+impl CalendarRange {
+    fn days(&self) -> u64 {
+        match self {
+            CalendarRange::Week => 7,
+            CalendarRange::Month => 31,
+        }
+    }
+}
+/// A scheduled episode of a bookmarked show
+#[derive(Debug, Clone)]
+pub struct UpcomingEpisode<'a> {
+    pub bookmark: &'a Bookmark,
+    pub episode: EpisodeDetails,
+    pub air_date: NaiveDate,
+}
+/// Upcoming episodes shown under a common heading
+#[derive(Debug)]
+pub struct CalendarGroup<'a> {
+    pub title: String,
+    /// True if the episodes should already have aired
+    pub past_due: bool,
+    pub episodes: Vec<UpcomingEpisode<'a>>,
+}
+impl State {
+    /// Exports the upcoming episodes as iCalendar file to `path`
+    pub fn export_calendar(&self, path: PathBuf) -> Command<Message> {
+        let ics = ics::to_ics(&self.upcoming_episodes(), &self.episode_notes);
+        Command::perform(save::write_export(path, ics), Message::CalendarExported)
+    }
+    /// Updates the subscribed calendar file if a path is configured.
+    /// Only one write is running at a time, changes in the meantime are written afterwards
+    pub fn write_calendar_subscription(&mut self) -> Command<Message> {
+        let Some(path) = self.config.calendar_path.clone() else {
+            return Command::none();
+        };
+        self.gui.calendar_dirty = true;
+        if self.gui.calendar_writing {
+            return Command::none();
+        }
+        self.gui.calendar_dirty = false;
+        self.gui.calendar_writing = true;
+        let ics = ics::to_ics(&self.upcoming_episodes(), &self.episode_notes);
+        Command::perform(
+            async move {
+                let result = save::write_export(path, ics).await;
+                // Responses arrive in bursts, collect them into one write
+                async_std::task::sleep(CALENDAR_WRITE_DELAY).await;
+                result
+            },
+            Message::CalendarSubscriptionWritten,
+        )
+    }
+}
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+impl State {
+    /// All scheduled episodes of bookmarks which are not dropped, ordered by air date.
+    /// Besides the next episode to air, the remaining episodes of its season are included if known
+    pub fn upcoming_episodes(&self) -> Vec<UpcomingEpisode<'_>> {
+        let mut upcoming: Vec<UpcomingEpisode> = Vec::new();
+        for bookmark in self
+            .bookmarks
+            .iter()
+            .filter(|bookmark| bookmark.status() != WatchStatus::Dropped)
+        {
+            let id = bookmark.movie.id;
+            let Some(next) = self
+                .movie_details
+                .get(&id)
+                .and_then(|details| details.next_episode_to_air())
+            else {
+                continue;
+            };
+            let scheduled = self
+                .season_details
+                .get(&id)
+                .filter(|season| season.season_number == next.episode.season_number)
+                .map(|season| {
+                    season
+                        .episodes
+                        .iter()
+                        .filter(|episode| {
+                            episode.episode.episode_number >= next.episode.episode_number
+                        })
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_else(|| vec![next]);
+            upcoming.extend(scheduled.into_iter().filter_map(|episode| {
+                Some(UpcomingEpisode {
+                    bookmark,
+                    air_date: episode.air_date?,
+                    episode,
+                })
+            }));
+        }
+        upcoming.sort_by_key(|upcoming| upcoming.air_date);
+        upcoming
+    }
+}
+/// Groups the episodes by day or week depending on the range.
+/// Episodes before `today` are collected in a separate group and episodes after the range are dropped
+pub fn group_episodes<'a>(
+    episodes: Vec<UpcomingEpisode<'a>>,
+    range: CalendarRange,
+    today: NaiveDate,
+) -> Vec<CalendarGroup<'a>> {
+    let end = today + Days::new(range.days());
+    let mut groups: Vec<CalendarGroup> = Vec::new();
+    for episode in episodes {
+        if episode.air_date >= end {
+            continue;
+        }
+        let (title, past_due) = if episode.air_date < today {
+            ("Past due".to_owned(), true)
+        } else {
+            match range {
+                CalendarRange::Week => (
+                    format!(
+                        "{} · {}",
+                        episode.air_date.format("%A, %d %B"),
+                        relative_date(episode.air_date, today)
+                    ),
+                    false,
+                ),
+                CalendarRange::Month => {
+                    let week_start = episode.air_date.week(chrono::Weekday::Mon).first_day();
+                    (format!("Week of {}", week_start.format("%d %B")), false)
+                }
+            }
+        };
+        match groups.last_mut() {
+            Some(group) if group.title == title => group.episodes.push(episode),
+            _ => groups.push(CalendarGroup {
+                title,
+                past_due,
+                episodes: vec![episode],
+            }),
+        }
+    }
+    groups
+}
+/// Describes `date` relative to `today`, e.g. `in 2 days`
+pub fn relative_date(date: NaiveDate, today: NaiveDate) -> String {
+    let days = date.num_days_from_ce() - today.num_days_from_ce();
+    match days {
+        0 => "today".into(),
+        1 => "tomorrow".into(),
+        -1 => "yesterday".into(),
+        2.. => format!("in {days} days"),
+        _ => format!("{} days ago", -days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::movie::test_movie;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+    fn upcoming(
+        bookmark: &Bookmark,
+        episode_number: usize,
+        air_date: NaiveDate,
+    ) -> UpcomingEpisode<'_> {
+        let episode = serde_json::from_value(json!({
+            "episode_number": episode_number,
+            "season_number": 1,
+            "name": "",
+            "air_date": air_date.to_string(),
+            "overview": "",
+        }))
+        .unwrap();
+        UpcomingEpisode {
+            bookmark,
+            episode,
+            air_date,
+        }
+    }
+    fn groups(groups: &[CalendarGroup]) -> Vec<(String, bool, Vec<usize>)> {
+        groups
+            .iter()
+            .map(|group| {
+                let episodes = group
+                    .episodes
+                    .iter()
+                    .map(|upcoming| upcoming.episode.episode.episode_number)
+                    .collect();
+                (group.title.clone(), group.past_due, episodes)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn describes_dates_relative_to_today() {
+        let today = date(3, 13);
+        assert_eq!(relative_date(date(3, 13), today), "today");
+        assert_eq!(relative_date(date(3, 14), today), "tomorrow");
+        assert_eq!(relative_date(date(3, 12), today), "yesterday");
+        assert_eq!(relative_date(date(4, 2), today), "in 20 days");
+        assert_eq!(relative_date(date(2, 28), today), "14 days ago");
+        assert_eq!(
+            relative_date(date(3, 13), NaiveDate::from_ymd_opt(2023, 3, 13).unwrap()),
+            "in 366 days"
+        );
+    }
+    #[test]
+    fn groups_episodes_by_day_and_week() {
+        let bookmark = Bookmark::from(&test_movie(1, "Show"));
+        // 2024-03-13 is a wednesday
+        let episodes = || {
+            vec![
+                upcoming(&bookmark, 1, date(3, 1)),
+                upcoming(&bookmark, 2, date(3, 10)),
+                upcoming(&bookmark, 3, date(3, 13)),
+                upcoming(&bookmark, 4, date(3, 13)),
+                upcoming(&bookmark, 5, date(3, 14)),
+                upcoming(&bookmark, 6, date(3, 19)),
+                upcoming(&bookmark, 7, date(3, 20)),
+                upcoming(&bookmark, 8, date(4, 14)),
+            ]
+        };
+        assert_eq!(
+            groups(&group_episodes(
+                episodes(),
+                CalendarRange::Week,
+                date(3, 13)
+            )),
+            [
+                ("Past due".to_owned(), true, vec![1, 2]),
+                ("Wednesday, 13 March · today".to_owned(), false, vec![3, 4]),
+                ("Thursday, 14 March · tomorrow".to_owned(), false, vec![5]),
+                ("Tuesday, 19 March · in 6 days".to_owned(), false, vec![6]),
+            ]
+        );
+        assert_eq!(
+            groups(&group_episodes(
+                episodes(),
+                CalendarRange::Month,
+                date(3, 13)
+            )),
+            [
+                ("Past due".to_owned(), true, vec![1, 2]),
+                ("Week of 11 March".to_owned(), false, vec![3, 4, 5]),
+                ("Week of 18 March".to_owned(), false, vec![6, 7]),
+            ]
+        );
+        assert!(group_episodes(Vec::new(), CalendarRange::Week, date(3, 13)).is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, Text};
+use iced::{Point, Rectangle, Renderer, Size, Theme};
+
+/// Height reserved for the value above and the label below each bar
+const LABEL_HEIGHT: f32 = 20.;
+const LABEL_SIZE: f32 = 14.;
+
+/// Vertical bars in the primary color of the theme, labeled below and with their value above
+#[derive(Debug, Clone)]
+pub struct BarChart {
+    pub bars: Vec<(String, usize)>,
+}
+impl<Message> canvas::Program<Message> for BarChart {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let palette = theme.palette();
+        let max = self
+            .bars
+            .iter()
+            .map(|(_, value)| *value)
+            .max()
+            .unwrap_or(0)
+            .max(1) as f32;
+        let slot = bounds.width / self.bars.len().max(1) as f32;
+        let bar_width = slot * 0.7;
+        let chart_height = (bounds.height - 2. * LABEL_HEIGHT).max(0.);
+        for (index, (label, value)) in self.bars.iter().enumerate() {
+            let height = chart_height * *value as f32 / max;
+            let center = slot * (index as f32 + 0.5);
+            let top = LABEL_HEIGHT + chart_height - height;
+            frame.fill_rectangle(
+                Point::new(center - bar_width / 2., top),
+                Size::new(bar_width, height),
+                palette.primary,
+            );
+            frame.fill_text(Text {
+                content: value.to_string(),
+                position: Point::new(center, top - 2.),
+                color: palette.text,
+                size: LABEL_SIZE.into(),
+                horizontal_alignment: Horizontal::Center,
+                vertical_alignment: Vertical::Bottom,
+                ..Text::default()
+            });
+            frame.fill_text(Text {
+                content: label.clone(),
+                position: Point::new(center, bounds.height),
+                color: palette.text,
+                size: LABEL_SIZE.into(),
+                horizontal_alignment: Horizontal::Center,
+                vertical_alignment: Vertical::Bottom,
+                ..Text::default()
+            });
+        }
+        vec![frame.into_geometry()]
+    }
+}
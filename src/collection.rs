@@ -0,0 +1,45 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bookmark::Bookmark;
+use crate::state::State;
+
+/// Saved tag filter which is shown as an extra tab
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Collection {
+    pub name: String,
+    /// Bookmarks need all of these tags to be part of the collection
+    pub tags: BTreeSet<String>,
+}
+impl Collection {
+    pub fn contains(&self, bookmark: &Bookmark) -> bool {
+        self.tags.is_subset(&bookmark.tags)
+    }
+}
+/// Normalizes user input to a tag. Returns `None` for empty input
+pub fn parse_tag(input: &str) -> Option<String> {
+    let tag = input.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!tag.is_empty()).then_some(tag)
+}
+impl State {
+    /// Tags used by any bookmark in alphabetical order
+    pub fn all_tags(&self) -> BTreeSet<&String> {
+        self.bookmarks
+            .iter()
+            .flat_map(|bookmark| bookmark.tags.iter())
+            .collect()
+    }
+    /// Saves the selected tags as collection and shows it
+    pub fn save_collection(&mut self, name: &str) -> Option<usize> {
+        let name = name.trim();
+        if name.is_empty() || self.gui.tag_filter.is_empty() {
+            return None;
+        }
+        self.collections.push(Collection {
+            name: name.to_owned(),
+            tags: std::mem::take(&mut self.gui.tag_filter),
+        });
+        Some(self.collections.len() - 1)
+    }
+}
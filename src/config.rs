@@ -0,0 +1,337 @@
+use std::{fmt::Display, path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::launch;
+use crate::save;
+use crate::sort::SortOrder;
+use crate::theme::ThemeChoice;
+
+/// User configuration which is persisted as TOML file next to the state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Minutes after which the details of a bookmark are requested again
+    pub refresh_interval_minutes: u64,
+    /// If set, the calendar of upcoming episodes is kept up to date at this path
+    pub calendar_path: Option<PathBuf>,
+    /// Command used to open links with the system url handler
+    pub url_opener: Vec<String>,
+    /// Command used to play local episodes
+    pub player: Vec<String>,
+    /// What happens with the progress after a link or local episode was used
+    pub advance_on_play: AdvanceMode,
+    /// Advance bookmarks only after mpv played this fraction of the episode
+    pub track_player_progress: bool,
+    /// Fraction of the episode after which it counts as watched
+    pub watched_threshold: f64,
+    /// Initial size of the window
+    pub window_width: f32,
+    pub window_height: f32,
+    /// Width of a bookmark card. As many columns as fit into the window are shown
+    pub card_width: f32,
+    pub bookmark_layout: BookmarkLayout,
+    pub sort: SortOrder,
+    /// Language of the texts requested from TMDB
+    pub language: String,
+    /// Seconds to wait after saving before the state is saved again
+    pub save_delay_seconds: u64,
+    pub theme: ThemeChoice,
+    // Tables have to come after all plain values in TOML
+    pub fonts: FontSizes,
+    /// Hours in which no notifications are shown
+    pub quiet_hours: Option<QuietHours>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FontSizes {
+    pub text: u16,
+    pub header: u16,
+}
+impl Default for FontSizes {
+    fn default() -> Self {
+        Self {
+            text: 22,
+            header: 30,
+        }
+    }
+}
+/// Whether using a link advances the bookmark to the next episode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdvanceMode {
+    #[default]
+    Always,
+    Never,
+    /// Ask after playing whether the episode was watched
+    Ask,
+}
+impl AdvanceMode {
+    pub const ALL: [AdvanceMode; 3] = [AdvanceMode::Always, AdvanceMode::Never, AdvanceMode::Ask];
+}
+impl Display for AdvanceMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AdvanceMode::Always => "Always advance",
+            AdvanceMode::Never => "Never advance",
+            AdvanceMode::Ask => "Ask",
+        };
+        write!(f, "{name}")
+    }
+}
+/// How the bookmarks are laid out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BookmarkLayout {
+    /// Cards with posters, as many columns as fit
+    #[default]
+    Grid,
+    /// One compact row per bookmark
+    List,
+}
+impl BookmarkLayout {
+    pub const ALL: [BookmarkLayout; 2] = [BookmarkLayout::Grid, BookmarkLayout::List];
+}
+impl Display for BookmarkLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BookmarkLayout::Grid => "Grid",
+            BookmarkLayout::List => "List",
+        };
+        write!(f, "{name}")
+    }
+}
+/// Range of hours of the day, `end` being exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: u32,
+    pub end: u32,
+}
+impl Default for QuietHours {
+    fn default() -> Self {
+        Self { start: 22, end: 8 }
+    }
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            refresh_interval_minutes: 60,
+            calendar_path: None,
+            quiet_hours: None,
+            url_opener: launch::default_opener(),
+            player: launch::default_opener(),
+            advance_on_play: AdvanceMode::Always,
+            track_player_progress: true,
+            watched_threshold: 0.85,
+            window_width: 800.,
+            window_height: 500.,
+            fonts: FontSizes::default(),
+            card_width: 280.,
+            bookmark_layout: BookmarkLayout::Grid,
+            sort: SortOrder::default(),
+            language: "en-US".into(),
+            save_delay_seconds: 5,
+            theme: ThemeChoice::default(),
+        }
+    }
+}
+impl Config {
+    pub fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.refresh_interval_minutes.max(1) * 60)
+    }
+    pub fn save_delay(&self) -> Duration {
+        Duration::from_secs(self.save_delay_seconds)
+    }
+    /// Reads the config file. Returns `None` if there is no config file yet
+    pub fn load() -> Result<Option<Config>, String> {
+        let path = save::config_path();
+        if !path.is_file() {
+            info!("no config file at {path:?}");
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        toml::from_str(&contents)
+            .map(Some)
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+/// Settings which are edited as text on the settings page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Setting {
+    RefreshInterval,
+    SaveDelay,
+    Language,
+    WindowWidth,
+    WindowHeight,
+    FontSize,
+    HeaderFontSize,
+    UrlOpener,
+    Player,
+    WatchedThreshold,
+}
+impl Setting {
+    pub const ALL: [Setting; 10] = [
+        Setting::RefreshInterval,
+        Setting::SaveDelay,
+        Setting::Language,
+        Setting::WindowWidth,
+        Setting::WindowHeight,
+        Setting::FontSize,
+        Setting::HeaderFontSize,
+        Setting::UrlOpener,
+        Setting::Player,
+        Setting::WatchedThreshold,
+    ];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Setting::RefreshInterval => "Refresh interval (minutes)",
+            Setting::SaveDelay => "Save delay (seconds)",
+            Setting::Language => "Language",
+            Setting::WindowWidth => "Window width",
+            Setting::WindowHeight => "Window height",
+            Setting::FontSize => "Font size",
+            Setting::HeaderFontSize => "Header font size",
+            Setting::UrlOpener => "Browser command",
+            Setting::Player => "Player command",
+            Setting::WatchedThreshold => "Watched after (%)",
+        }
+    }
+    /// The current value as text
+    pub fn get(&self, config: &Config) -> String {
+        match self {
+            Setting::RefreshInterval => config.refresh_interval_minutes.to_string(),
+            Setting::SaveDelay => config.save_delay_seconds.to_string(),
+            Setting::Language => config.language.clone(),
+            Setting::WindowWidth => config.window_width.to_string(),
+            Setting::WindowHeight => config.window_height.to_string(),
+            Setting::FontSize => config.fonts.text.to_string(),
+            Setting::HeaderFontSize => config.fonts.header.to_string(),
+            Setting::UrlOpener => launch::join_arguments(&config.url_opener),
+            Setting::Player => launch::join_arguments(&config.player),
+            Setting::WatchedThreshold => (config.watched_threshold * 100.).round().to_string(),
+        }
+    }
+    /// Parses the input and applies it to the config.
+    /// The config stays unchanged if the input is invalid
+    pub fn set(&self, config: &mut Config, input: &str) -> Result<(), String> {
+        let input = input.trim();
+        match self {
+            Setting::RefreshInterval => {
+                config.refresh_interval_minutes = parse_in(input, 1, 10080)?
+            }
+            Setting::SaveDelay => config.save_delay_seconds = parse_in(input, 0, 600)?,
+            Setting::Language => {
+                if input.is_empty() {
+                    return Err("Enter a language like en-US".into());
+                }
+                config.language = input.to_owned();
+            }
+            Setting::WindowWidth => config.window_width = parse_in(input, 200., 10000.)?,
+            Setting::WindowHeight => config.window_height = parse_in(input, 200., 10000.)?,
+            Setting::FontSize => config.fonts.text = parse_in(input, 8, 72)?,
+            Setting::HeaderFontSize => config.fonts.header = parse_in(input, 8, 96)?,
+            Setting::UrlOpener => config.url_opener = parse_command(input)?,
+            Setting::Player => config.player = parse_command(input)?,
+            Setting::WatchedThreshold => {
+                config.watched_threshold = parse_in::<f64>(input, 1., 100.)? / 100.;
+            }
+        }
+        Ok(())
+    }
+}
+/// Parses a number within `min..=max`
+fn parse_in<T>(input: &str, min: T, max: T) -> Result<T, String>
+where
+    T: std::str::FromStr + PartialOrd + Display,
+{
+    let value: T = input
+        .parse()
+        .map_err(|_| format!("{input} is not a number"))?;
+    if value < min || value > max {
+        return Err(format!("Must be between {min} and {max}"));
+    }
+    Ok(value)
+}
+fn parse_command(input: &str) -> Result<Vec<String>, String> {
+    let argv = launch::split_arguments(input);
+    if argv.is_empty() {
+        return Err("Enter a command".into());
+    }
+    Ok(argv)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_numbers_within_range() {
+        assert_eq!(parse_in("8", 8, 72), Ok(8));
+        assert_eq!(parse_in("72", 8, 72), Ok(72));
+        assert_eq!(parse_in("0.5", 0., 1.), Ok(0.5));
+        assert_eq!(parse_in("7", 8, 72), Err("Must be between 8 and 72".into()));
+        assert_eq!(
+            parse_in("73", 8, 72),
+            Err("Must be between 8 and 72".into())
+        );
+        assert_eq!(
+            parse_in::<u16>("-1", 8, 72),
+            Err("-1 is not a number".into())
+        );
+        assert_eq!(
+            parse_in::<u64>("ten", 1, 10),
+            Err("ten is not a number".into())
+        );
+    }
+
+    #[test]
+    fn invalid_input_keeps_the_config() {
+        let mut config = Config::default();
+        let before = config.clone();
+        assert!(Setting::RefreshInterval.set(&mut config, "0").is_err());
+        assert!(Setting::SaveDelay.set(&mut config, "601").is_err());
+        assert!(Setting::WindowWidth.set(&mut config, "wide").is_err());
+        assert!(Setting::HeaderFontSize.set(&mut config, "97").is_err());
+        assert!(Setting::WatchedThreshold.set(&mut config, "0").is_err());
+        assert!(Setting::Language.set(&mut config, "  ").is_err());
+        assert!(Setting::UrlOpener.set(&mut config, " ").is_err());
+        assert!(Setting::Player.set(&mut config, "").is_err());
+        assert_eq!(config, before);
+    }
+
+    #[test]
+    fn valid_input_is_applied() {
+        let mut config = Config::default();
+        Setting::RefreshInterval
+            .set(&mut config, " 10080 ")
+            .unwrap();
+        Setting::SaveDelay.set(&mut config, "0").unwrap();
+        Setting::Language.set(&mut config, "de-DE").unwrap();
+        Setting::FontSize.set(&mut config, "16").unwrap();
+        Setting::WatchedThreshold.set(&mut config, "90").unwrap();
+        assert_eq!(config.refresh_interval_minutes, 10080);
+        assert_eq!(config.save_delay_seconds, 0);
+        assert_eq!(config.language, "de-DE");
+        assert_eq!(config.fonts.text, 16);
+        assert_eq!(config.watched_threshold, 0.9);
+        assert_eq!(Setting::WatchedThreshold.get(&config), "90");
+    }
+
+    #[test]
+    fn commands_are_split_into_arguments() {
+        let mut config = Config::default();
+        Setting::Player
+            .set(&mut config, r#"mpv --title "Some Show" --fs"#)
+            .unwrap();
+        assert_eq!(config.player, ["mpv", "--title", "Some Show", "--fs"]);
+        Setting::UrlOpener
+            .set(&mut config, r#""C:\Program Files\browser.exe" --new-tab"#)
+            .unwrap();
+        assert_eq!(
+            config.url_opener,
+            [r"C:\Program Files\browser.exe", "--new-tab"]
+        );
+        assert_eq!(
+            Setting::UrlOpener.get(&config),
+            r#""C:\Program Files\browser.exe" --new-tab"#
+        );
+    }
+}
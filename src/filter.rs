@@ -1,13 +1,23 @@
 use serde::{Deserialize, Serialize};
 
-use crate::id::MovieId;
+use crate::{bookmark::WatchStatus, id::MovieId};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum Filter {
     #[default]
     Bookmarks,
     Search,
+    PlanToWatch,
+    OnHold,
+    Dropped,
     Completed,
+    Archived,
+    Calendar,
+    Notifications,
+    Stats,
+    Settings,
+    /// Saved collection by its index
+    Collection(usize),
     Details(MovieId),
 }
 
@@ -16,8 +26,39 @@ impl Filter {
         match self {
             Filter::Bookmarks => "You have no bookmarks yet",
             Filter::Search => "Type in a search  term",
+            Filter::PlanToWatch => "You have no planned movies yet",
+            Filter::OnHold => "You have no movies on hold",
+            Filter::Dropped => "You have no dropped movies",
             Filter::Completed => "You have no completed movies yet",
+            Filter::Archived => "No deleted bookmarks",
+            Filter::Calendar => "No upcoming episodes",
+            Filter::Notifications => "No notifications yet",
+            Filter::Stats => "No episodes watched yet",
+            Filter::Settings => "No settings available",
+            Filter::Collection(_) => "No bookmarks have the tags of this collection",
             Filter::Details(_) => "Selected movie has not details",
         }
     }
+    /// The status of the bookmarks shown in this filter, if the filter shows bookmarks
+    pub fn watch_status(&self) -> Option<WatchStatus> {
+        match self {
+            Filter::Bookmarks => Some(WatchStatus::Watching),
+            Filter::PlanToWatch => Some(WatchStatus::PlanToWatch),
+            Filter::OnHold => Some(WatchStatus::OnHold),
+            Filter::Dropped => Some(WatchStatus::Dropped),
+            Filter::Completed => Some(WatchStatus::Completed),
+            Filter::Search
+            | Filter::Archived
+            | Filter::Calendar
+            | Filter::Notifications
+            | Filter::Stats
+            | Filter::Settings
+            | Filter::Collection(_)
+            | Filter::Details(_) => None,
+        }
+    }
+    /// True if the filter shows a list of bookmarks
+    pub fn shows_bookmarks(&self) -> bool {
+        self.watch_status().is_some() || matches!(self, Filter::Collection(_))
+    }
 }
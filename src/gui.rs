@@ -1,31 +1,50 @@
-use std::iter::once;
-
+use crate::collection::parse_tag;
 use crate::filter::Filter;
-use crate::id::{EpisodeId, MovieIndex};
+use crate::id::MovieIndex;
 use crate::movie_details::EpisodeDetails;
 use crate::save::{LoadError, SavedState};
+use crate::selection::BulkAction;
+use crate::sort::SortOrder;
 use crate::state::{GuiState, InputKind, State};
+use crate::theme::{ThemeChoice, ViewStyle};
 use crate::view;
 use iced::alignment::{self, Alignment, Horizontal, Vertical};
 use iced::theme::{self, Theme};
 use iced::widget::{
-    button, column, container, keyed_column, row, scrollable, text, text_input, Space,
+    button, checkbox, column, container, keyed_column, mouse_area, pick_list, row, scrollable,
+    slider, text, text_input, Space,
 };
-use iced::{keyboard, window};
+use iced::{event, keyboard, mouse, time, window, Event};
 use iced::{Application, Element};
-use iced::{Color, Command, Length, Subscription};
+use iced::{Command, Length, Subscription};
 use once_cell::sync::Lazy;
 use tracing::error;
 
-use crate::bookmark::Bookmark;
-use crate::message::{empty_message, loading_message, Message, ShiftPressed};
+use crate::bookmark::{Bookmark, WatchStatus};
+use crate::config::{BookmarkLayout, FontSizes};
+use crate::icons::{Icon, ICON_SIZE};
+use crate::id::MovieId;
+use crate::message::{empty_message, loading_message, Message};
+use std::time::Duration;
 
-pub static FONT_SIZE_HEADER: u16 = 30;
-pub static FONT_SIZE: u16 = 22;
 const TITLE_NAME: &str = "Webworm";
-static INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
-static FG_COLOR: Color = Color::from_rgb(0.5, 0.5, 0.5);
-static ERROR_COLOR: Color = Color::from_rgb(1., 0.2, 0.2);
+pub(crate) static INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+pub(crate) static SCROLLABLE_ID: Lazy<scrollable::Id> = Lazy::new(scrollable::Id::unique);
+/// Lower bound for the time between two refreshes of bookmark details
+const MIN_REFRESH_TICK: Duration = Duration::from_secs(30);
+/// How often the system is asked whether it switched between light and dark mode
+const SYSTEM_THEME_TICK: Duration = Duration::from_secs(30);
+/// How often held back notifications are checked for the end of the quiet hours
+const HELD_BACK_TICK: Duration = Duration::from_secs(60);
+/// Padding around the page
+const PAGE_PADDING: f32 = 40.;
+/// Space between the bookmark cards
+const GRID_SPACING: f32 = 50.;
+/// Range of the card size slider
+const MIN_CARD_WIDTH: f32 = 160.;
+const MAX_CARD_WIDTH: f32 = 480.;
+/// Width of pages which don't show bookmarks
+const PAGE_MAX_WIDTH: f32 = 1000.;
 
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
@@ -84,148 +103,167 @@ impl Application for App {
         }
     }
 
-    fn view(&self) -> Element<Message> {
+    fn theme(&self) -> Theme {
+        match self {
+            App::Loaded(state) => state.theme.clone(),
+            _ => Theme::default(),
+        }
+    }
+
+    fn view(&self) -> Element<'_, Message> {
         match self {
             App::Loading => loading_message(),
-            App::Loaded(State {
-                gui:
-                    GuiState {
-                        input_caches,
-                        filter,
-                        ..
-                    },
-                movies,
-                movie_details,
-                movie_posters,
-                episode_details,
-                bookmarks,
-                ..
-            }) => {
-                let header = view_header();
-                let input = view_input(&input_caches[InputKind::SearchField]);
+            App::Loaded(
+                state @ State {
+                    gui:
+                        GuiState {
+                            input_caches,
+                            filter,
+                            ..
+                        },
+                    movies,
+                    movie_details,
+                    movie_posters,
+                    episode_details,
+                    bookmarks,
+                    new_since_launch,
+                    ..
+                },
+            ) => {
+                let style = state.view_style();
+                let header = view_header(style);
+                let input = view_input(&input_caches[InputKind::SearchField], style);
                 let mut control_info = None;
                 let body = match filter {
                     Filter::Search => {
                         if movies.is_empty() {
-                            empty_message(filter.empty_message())
+                            empty_message(filter.empty_message(), style)
                         } else {
                             control_info =
                                 Some(format!("{} results found for search", movies.len()));
                             keyed_column(
-                                movies
-                                    .iter()
+                                state
+                                    .sorted_movies()
+                                    .into_iter()
                                     .map(|task| (task.id, task.view(movie_posters.get(&task.id)))),
                             )
                             .spacing(10)
                             .into()
                         }
                     }
-                    Filter::Bookmarks | Filter::Completed => {
+                    Filter::Bookmarks
+                    | Filter::PlanToWatch
+                    | Filter::OnHold
+                    | Filter::Dropped
+                    | Filter::Completed
+                    | Filter::Collection(_) => {
                         if bookmarks.is_empty() {
-                            empty_message(filter.empty_message())
+                            empty_message(filter.empty_message(), style)
                         } else {
-                            let bookmarks: Vec<&Bookmark> = bookmarks
-                                .iter()
-                                .filter(|bookmark| {
-                                    if Filter::Completed == *filter {
-                                        bookmark.finished
-                                    } else {
-                                        !bookmark.finished
-                                    }
-                                })
-                                .filter(|bookmark| {
-                                    bookmark
-                                        .movie
-                                        .matches_filter(&input_caches[InputKind::SearchField])
-                                })
-                                .collect();
+                            let bookmarks = state.filtered_bookmarks();
+                            let rewatching = state.rewatching_bookmarks();
                             control_info = Some(format!("{} movies left", bookmarks.len()));
-                            let chunk_size = 3;
-                            let chunks = bookmarks.chunks_exact(chunk_size);
-                            let remainder = chunks.remainder();
-                            let remainder_row =
-                                (0..chunk_size).map(|i| remainder.get(i)).map(|bookmark| {
-                                    if let Some(bookmark) = bookmark {
-                                        bookmark.card_view(
-                                            movie_details.get(&bookmark.movie.id),
-                                            movie_posters.get(&bookmark.movie.id),
+                            let grid = view_bookmarks(&bookmarks, state);
+                            if Filter::Bookmarks == *filter {
+                                let mut sections = column![].spacing(50);
+                                if !new_since_launch.is_empty() {
+                                    sections = sections.push(view_new_episodes(
+                                        new_since_launch,
+                                        &state.bookmarks,
+                                        style,
+                                    ));
+                                }
+                                sections = sections.push(grid);
+                                if !rewatching.is_empty() {
+                                    sections = sections
+                                        .push(
+                                            text("Rewatching")
+                                                .size(style.fonts.header)
+                                                .style(style.muted),
                                         )
-                                    } else {
-                                        Space::with_width(Length::Fill).into()
-                                    }
-                                });
-                            column(
-                                chunks
-                                    .map(|bookmarks| {
-                                        bookmarks.iter().map(|bookmark| {
-                                            bookmark.card_view(
-                                                movie_details.get(&bookmark.movie.id),
-                                                movie_posters.get(&bookmark.movie.id),
-                                            )
-                                        })
-                                    })
-                                    .map(|it| row(it).spacing(50).into())
-                                    .chain(once(row(remainder_row).into())),
-                            )
-                            .spacing(50)
-                            .into()
+                                        .push(view_bookmarks(&rewatching, state));
+                                }
+                                sections.into()
+                            } else {
+                                grid
+                            }
                         }
                     }
+                    Filter::Calendar => view::view_calendar(state),
+                    Filter::Notifications => view::view_notifications(state),
+                    Filter::Stats => view::view_stats(state),
+                    Filter::Archived => view::view_archive(state),
+                    Filter::Settings => view::view_settings(state),
                     Filter::Details(id) => {
                         let bookmark = bookmarks
                             .with_id(*id)
                             .expect("tried to show details for bookmark that does not exist");
-                        let movie = &bookmark.movie;
                         let details = movie_details.get(id);
                         let poster = movie_posters.get(id);
                         let current_episode_details = episode_details
-                            .get(&EpisodeId(*id, bookmark.current_episode.clone()))
+                            .get(&bookmark.current_episode_id())
                             .cloned()
                             .or_else(|| {
                                 if let Some(details) = details {
                                     let seasonal =
-                                        details.as_seasonal_episode(&bookmark.current_episode);
+                                        details.as_seasonal_episode(bookmark.active_episode());
                                     Some(EpisodeDetails {
                                         episode: seasonal,
                                         name: "".into(),
                                         air_date: None,
                                         overview: "".into(),
+                                        runtime: None,
                                     })
                                 } else {
                                     None
                                 }
                             });
                         view::view_details(
-                            movie,
-                            input_caches,
+                            bookmark,
+                            state,
                             details,
                             poster,
                             current_episode_details,
-                            bookmark.sync_mode,
                         )
                     }
                 };
-                let controls = view_controls(control_info, *filter);
-                let content = match filter {
-                    Filter::Bookmarks | Filter::Search | Filter::Completed => {
-                        column![header, input, controls, body]
-                    }
-                    Filter::Details(_) => column![header, body],
+                let controls = view_controls(control_info, state);
+                let mut content = column![header].spacing(20);
+                if !filter.shows_bookmarks() {
+                    content = content.max_width(PAGE_MAX_WIDTH);
+                }
+                for prompt in view_advance_prompts(state) {
+                    content = content.push(prompt);
                 }
-                .spacing(20)
-                .max_width(1000);
+                if let Some(prompt) = view_archive_prompt(state) {
+                    content = content.push(prompt);
+                }
+                if let Some(prompt) = view_bulk_prompt(state) {
+                    content = content.push(prompt);
+                }
+                if let Some(undo) = view_undo(state) {
+                    content = content.push(undo);
+                }
+                let content = match filter {
+                    _ if state.gui.show_keymap => content.push(view::view_keymap(state)),
+                    Filter::Details(_) => content.push(body),
+                    _ => content.push(input).push(controls).push(body),
+                };
 
-                scrollable(container(content).padding(40).center_x()).into()
+                scrollable(container(content).padding(PAGE_PADDING).center_x())
+                    .id(SCROLLABLE_ID.clone())
+                    .into()
             }
             App::CreateNew(error) => {
+                let style = ViewStyle::new(&Theme::default(), FontSizes::default());
                 let create = button("OVERRIDE OLD STATE").on_press(Message::CreateNew);
                 container(
                     column![
                         row![
                             text("State loading failed: ")
-                                .size(FONT_SIZE)
-                                .style(FG_COLOR),
-                            text(error).size(FONT_SIZE).style(ERROR_COLOR),
+                                .size(style.fonts.text)
+                                .style(style.muted),
+                            text(error).size(style.fonts.text).style(style.error),
                         ]
                         .spacing(20),
                         create
@@ -243,42 +281,220 @@ impl Application for App {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        use keyboard::key;
-        let on_press = keyboard::on_key_press(|key, modifiers| {
-            let keyboard::Key::Named(key) = key else {
-                return None;
-            };
-
-            match (key, modifiers) {
-                (key::Named::Tab, _) => Some(Message::TabPressed),
-                (key::Named::ArrowUp, keyboard::Modifiers::SHIFT) => {
-                    Some(Message::ToggleFullscreen(window::Mode::Fullscreen))
-                }
-                (key::Named::ArrowDown, keyboard::Modifiers::SHIFT) => {
-                    Some(Message::ToggleFullscreen(window::Mode::Windowed))
-                }
-                (key::Named::Shift, _) => Some(Message::ShiftPressed(ShiftPressed::True)),
-                _ => None,
+        // Resolved by the keymap of the state
+        let on_press =
+            keyboard::on_key_press(|key, modifiers| Some(Message::KeyPressed(key, modifiers)));
+        let modifiers = event::listen_with(|event, _status| match event {
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                Some(Message::ModifiersChanged(modifiers))
             }
+            _ => None,
         });
-        let on_release = keyboard::on_key_release(|key, _modifier| {
-            let keyboard::Key::Named(key) = key else {
-                return None;
-            };
-            if key == key::Named::Shift {
-                Some(Message::ShiftPressed(ShiftPressed::False))
-            } else {
-                None
+        let resize = event::listen_with(|event, _status| match event {
+            Event::Window(window::Id::MAIN, window::Event::Resized { width, .. }) => {
+                Some(Message::WindowResized(width as f32))
             }
+            _ => None,
         });
-        Subscription::batch([on_press, on_release])
+        let drag_end = match self {
+            App::Loaded(state) if state.gui.dragging.is_some() => {
+                event::listen_with(|event, _status| match event {
+                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                        Some(Message::DragEnd)
+                    }
+                    _ => None,
+                })
+            }
+            _ => Subscription::none(),
+        };
+        let refresh = match self {
+            App::Loaded(state) if !state.bookmarks.is_empty() => {
+                // Stagger the refreshes over the interval instead of refreshing all at once
+                let tick = state.config.refresh_interval() / state.bookmarks.len() as u32;
+                time::every(tick.max(MIN_REFRESH_TICK)).map(|_| Message::RefreshTick)
+            }
+            _ => Subscription::none(),
+        };
+        let system_theme = match self {
+            App::Loaded(state) if state.config.theme == ThemeChoice::System => {
+                time::every(SYSTEM_THEME_TICK).map(|_| Message::DetectSystemTheme)
+            }
+            _ => Subscription::none(),
+        };
+        let held_back = match self {
+            App::Loaded(state) if state.has_held_back_notifications() => {
+                time::every(HELD_BACK_TICK).map(|_| Message::DeliverHeldBack)
+            }
+            _ => Subscription::none(),
+        };
+        Subscription::batch([
+            on_press,
+            modifiers,
+            resize,
+            drag_end,
+            refresh,
+            system_theme,
+            held_back,
+        ])
     }
 }
-fn view_controls(
-    control_info: Option<String>,
-    current_filter: Filter,
-) -> Element<'static, Message> {
-    let filter_button = |label, filter, current_filter| {
+/// Asks whether the played episodes were watched, one question per bookmark
+fn view_advance_prompts(state: &State) -> Vec<Element<'_, Message>> {
+    state
+        .gui
+        .advance_prompts
+        .iter()
+        .filter_map(|(id, episode)| {
+            let bookmark = state.bookmarks.with_id(*id)?;
+            let prompt = row![
+                text(format!(
+                    "Finished {} {}?",
+                    bookmark.movie.name,
+                    episode.as_info_str()
+                ))
+                .width(Length::Fill),
+                button(Icon::Check.svg())
+                    .on_press(Message::AnswerAdvance(*id, true))
+                    .width(Length::Fixed(ICON_SIZE * 2.)),
+                button(Icon::X.svg())
+                    .on_press(Message::AnswerAdvance(*id, false))
+                    .width(Length::Fixed(ICON_SIZE * 2.)),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center);
+            Some(prompt.into())
+        })
+        .collect()
+}
+/// Lists the bookmarks which gained episodes since the previous run
+fn view_new_episodes<'a>(
+    new_since_launch: &[(MovieId, usize)],
+    bookmarks: &'a Vec<Bookmark>,
+    style: ViewStyle,
+) -> Element<'a, Message> {
+    let header = row![
+        text("New since last launch")
+            .size(style.fonts.header)
+            .style(style.muted)
+            .width(Length::Fill),
+        button(Icon::X.svg())
+            .on_press(Message::DismissNewEpisodes)
+            .width(Length::Fixed(ICON_SIZE * 2.)),
+    ]
+    .align_items(Alignment::Center);
+    let entries = new_since_launch.iter().filter_map(|(id, count)| {
+        let bookmark = bookmarks.with_id(*id)?;
+        let episodes = if *count == 1 { "episode" } else { "episodes" };
+        Some(
+            button(text(format!(
+                "{}: {count} new {episodes}",
+                bookmark.movie.name
+            )))
+            .style(theme::Button::Text)
+            .on_press(Message::FilterChanged(Filter::Details(*id)))
+            .into(),
+        )
+    });
+    column![header, column(entries).spacing(5)]
+        .spacing(10)
+        .into()
+}
+/// Lays out the bookmarks as configured
+fn view_bookmarks<'a>(bookmarks: &[&'a Bookmark], state: &'a State) -> Element<'a, Message> {
+    let style = state.view_style();
+    let query = state.search_query();
+    match state.config.bookmark_layout {
+        BookmarkLayout::Grid => view_grid(bookmarks, state),
+        BookmarkLayout::List => column(bookmarks.iter().map(|bookmark| {
+            let id = bookmark.movie.id;
+            let draggable = state.config.sort == SortOrder::Manual;
+            let highlights = query.highlights(&bookmark.movie.name);
+            let row =
+                bookmark.list_view(state.movie_details.get(&id), draggable, &highlights, style);
+            let row = if state.gui.selecting {
+                row![select_box(id, state), row]
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .into()
+            } else {
+                row
+            };
+            drop_target(id, focus_frame(id, row, state), state)
+        }))
+        .spacing(5)
+        .into(),
+    }
+}
+/// Lets a dragged bookmark take the place of the bookmark with the id while sorting manually
+fn drop_target<'a>(
+    id: MovieId,
+    element: Element<'a, Message>,
+    state: &State,
+) -> Element<'a, Message> {
+    if state.config.sort == SortOrder::Manual {
+        mouse_area(element).on_enter(Message::DragOver(id)).into()
+    } else {
+        element
+    }
+}
+/// Checkbox selecting the bookmark for bulk actions
+fn select_box(id: MovieId, state: &State) -> Element<'static, Message> {
+    checkbox("", state.gui.selection.contains(&id))
+        .on_toggle(move |_| Message::ToggleSelected(id))
+        .into()
+}
+/// Marks the bookmark selected with the keyboard
+fn focus_frame<'a>(
+    id: MovieId,
+    element: Element<'a, Message>,
+    state: &State,
+) -> Element<'a, Message> {
+    let frame = container(element).padding(5);
+    if state.gui.focused == Some(id) {
+        frame.style(theme::Container::Box).into()
+    } else {
+        frame.into()
+    }
+}
+/// Number of cards of the configured width which fit next to each other into the window
+pub(crate) fn grid_columns(state: &State) -> usize {
+    let available = state.gui.window_width - 2. * PAGE_PADDING;
+    let columns = (available + GRID_SPACING) / (state.config.card_width + GRID_SPACING);
+    (columns.floor() as usize).max(1)
+}
+/// Lays out the bookmarks as cards in as many columns as fit
+fn view_grid<'a>(bookmarks: &[&'a Bookmark], state: &'a State) -> Element<'a, Message> {
+    let query = state.search_query();
+    let card = |bookmark: &'a Bookmark| {
+        let id = bookmark.movie.id;
+        let card = bookmark.card_view(
+            state.movie_details.get(&id),
+            state.movie_posters.get(&id),
+            state.details_refreshed.get(&id).copied(),
+            state.config.sort == SortOrder::Manual,
+            &query.highlights(&bookmark.movie.name),
+            state.view_style(),
+        );
+        let card = if state.gui.selecting {
+            column![select_box(id, state), card].into()
+        } else {
+            card
+        };
+        let card = drop_target(id, focus_frame(id, card, state), state);
+        container(card).width(Length::Fixed(state.config.card_width))
+    };
+    column(
+        bookmarks
+            .chunks(grid_columns(state))
+            .map(|bookmarks| bookmarks.iter().map(|bookmark| card(bookmark).into()))
+            .map(|it| row(it).spacing(GRID_SPACING).into()),
+    )
+    .spacing(GRID_SPACING)
+    .into()
+}
+fn view_controls(control_info: Option<String>, state: &State) -> Element<'static, Message> {
+    let current_filter = state.gui.filter;
+    let filter_button = |label: &str, filter, current_filter| {
         let label = text(label);
 
         let button = button(label).style(if filter == current_filter {
@@ -290,34 +506,265 @@ fn view_controls(
         button.on_press(Message::FilterChanged(filter)).padding(8)
     };
 
-    row![
-        text(control_info.unwrap_or_default()).width(Length::Fill),
+    let mut info_row = row![text(control_info.unwrap_or_default()).width(Length::Fill)]
+        .spacing(20)
+        .align_items(Alignment::Center);
+    if current_filter.shows_bookmarks() || current_filter == Filter::Search {
+        info_row = info_row.push(
+            pick_list(
+                &SortOrder::ALL[..],
+                Some(state.config.sort),
+                Message::SortChanged,
+            )
+            .placeholder("Sort by"),
+        );
+    }
+    if current_filter.shows_bookmarks() {
+        info_row = info_row.push(
+            pick_list(
+                &WatchStatus::ALL[..],
+                None::<WatchStatus>,
+                Message::BulkStatus,
+            )
+            .placeholder("Move all shown to"),
+        );
+        let layout = state.config.bookmark_layout;
+        if layout == BookmarkLayout::Grid {
+            info_row = info_row.push(
+                slider(
+                    MIN_CARD_WIDTH..=MAX_CARD_WIDTH,
+                    state.config.card_width,
+                    Message::CardWidthChanged,
+                )
+                .step(10.)
+                .width(Length::Fixed(150.)),
+            );
+        }
+        info_row = info_row.push(pick_list(
+            &BookmarkLayout::ALL[..],
+            Some(layout),
+            Message::BookmarkLayoutChanged,
+        ));
+        info_row = info_row.push(
+            button(text(if state.gui.selecting {
+                "Done"
+            } else {
+                "Select"
+            }))
+            .style(if state.gui.selecting {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            })
+            .on_press(Message::ToggleSelecting)
+            .padding(8),
+        );
+    }
+    column![
         row![
-            filter_button("Bookmarks", Filter::Bookmarks, current_filter),
+            filter_button("Watching", Filter::Bookmarks, current_filter),
+            filter_button("Planned", Filter::PlanToWatch, current_filter),
+            filter_button("On Hold", Filter::OnHold, current_filter),
+            filter_button("Dropped", Filter::Dropped, current_filter),
+            filter_button("Completed", Filter::Completed, current_filter),
+            filter_button("Archived", Filter::Archived, current_filter),
+            filter_button("Calendar", Filter::Calendar, current_filter),
+            filter_button("Notifications", Filter::Notifications, current_filter),
+            filter_button("Stats", Filter::Stats, current_filter),
+            filter_button("Settings", Filter::Settings, current_filter),
             filter_button("Search", Filter::Search, current_filter),
-            filter_button("Completed", Filter::Completed, current_filter,),
         ]
-        .width(Length::Shrink)
         .spacing(10)
+        .extend(
+            state
+                .collections
+                .iter()
+                .enumerate()
+                .map(|(index, collection)| {
+                    filter_button(&collection.name, Filter::Collection(index), current_filter)
+                        .into()
+                })
+        ),
+        view_tag_filter(state),
+        info_row,
+        view_selection_bar(state),
     ]
-    .spacing(20)
-    .align_items(Alignment::Center)
+    .spacing(10)
     .into()
 }
-fn view_header() -> Element<'static, Message> {
+/// Bulk actions for the selected bookmarks
+fn view_selection_bar(state: &State) -> Element<'static, Message> {
+    if !state.gui.selecting || !state.gui.filter.shows_bookmarks() {
+        return Space::with_height(Length::Shrink).into();
+    }
+    let style = state.view_style();
+    let count = state.selected_ids().len();
+    let tag_input = &state.gui.input_caches[InputKind::BulkTagInput];
+    let tag = parse_tag(tag_input);
+    let enabled = |message: Message| (count > 0).then_some(message);
+    let tag_action = |action: fn(String) -> BulkAction| {
+        tag.clone()
+            .filter(|_| count > 0)
+            .map(|tag| Message::Bulk(action(tag)))
+    };
+    let selection_row = row![
+        text(format!("{count} selected")).width(Length::Fill),
+        button(text("Select all shown"))
+            .style(theme::Button::Secondary)
+            .on_press(Message::SelectAllShown)
+            .padding(8),
+        button(text("Clear"))
+            .style(theme::Button::Secondary)
+            .on_press_maybe(enabled(Message::ClearSelection))
+            .padding(8),
+        text(state.gui.bookmarks_export.clone().unwrap_or_default()).style(style.muted),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    let action_row = row![
+        pick_list(&WatchStatus::ALL[..], None::<WatchStatus>, |status| {
+            Message::Bulk(BulkAction::SetStatus(status))
+        })
+        .placeholder("Set status"),
+        text_input("Tag", tag_input)
+            .on_input(|input| Message::InputChanged(InputKind::BulkTagInput, input))
+            .on_submit(Message::InputSubmit(InputKind::BulkTagInput))
+            .width(Length::Fixed(150.)),
+        button(text("Add tag"))
+            .on_press_maybe(tag_action(BulkAction::AddTag))
+            .padding(8),
+        button(text("Remove tag"))
+            .on_press_maybe(tag_action(BulkAction::RemoveTag))
+            .padding(8),
+        button(text("Toggle sync"))
+            .on_press_maybe(enabled(Message::Bulk(BulkAction::ToggleSync)))
+            .padding(8),
+        button(text("Refresh"))
+            .on_press_maybe(enabled(Message::Bulk(BulkAction::Refresh)))
+            .padding(8),
+        button(text("Export"))
+            .on_press_maybe(enabled(Message::Bulk(BulkAction::Export)))
+            .padding(8),
+        Space::with_width(Length::Fill),
+        button(text("Delete"))
+            .style(theme::Button::Destructive)
+            .on_press_maybe(enabled(Message::Bulk(BulkAction::Delete)))
+            .padding(8),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    column![selection_row, action_row].spacing(10).into()
+}
+/// Asks before a destructive bulk action is applied
+fn view_bulk_prompt(state: &State) -> Option<Element<'_, Message>> {
+    let action = state.gui.pending_bulk.as_ref()?;
+    let prompt = row![
+        text(action.question(state.selected_ids().len())).width(Length::Fill),
+        button(text("Confirm"))
+            .style(theme::Button::Destructive)
+            .on_press(Message::ConfirmBulk(true))
+            .padding(8),
+        button(text("Cancel"))
+            .style(theme::Button::Secondary)
+            .on_press(Message::ConfirmBulk(false))
+            .padding(8),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    Some(prompt.into())
+}
+/// Asks before a bookmark is archived or purged
+fn view_archive_prompt(state: &State) -> Option<Element<'_, Message>> {
+    let confirmation = state.gui.archive_confirmation?;
+    let prompt = row![
+        text(state.confirmation_question(confirmation)).width(Length::Fill),
+        button(text("Confirm"))
+            .style(theme::Button::Destructive)
+            .on_press(Message::ConfirmArchive(true))
+            .padding(8),
+        button(text("Cancel"))
+            .style(theme::Button::Secondary)
+            .on_press(Message::ConfirmArchive(false))
+            .padding(8),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    Some(prompt.into())
+}
+/// Offers to revert the last bulk action
+fn view_undo(state: &State) -> Option<Element<'_, Message>> {
+    let undo = state.gui.undo.as_ref()?;
+    let row = row![
+        text(&undo.description).width(Length::Fill),
+        button(text("Undo")).on_press(Message::Undo).padding(8),
+        button(Icon::X.svg())
+            .on_press(Message::DismissUndo)
+            .width(Length::Fixed(ICON_SIZE * 2.)),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    Some(row.into())
+}
+/// Toggles for all tags and saving the selected tags as collection
+fn view_tag_filter(state: &State) -> Element<'static, Message> {
+    let current_filter = state.gui.filter;
+    if !current_filter.shows_bookmarks() {
+        return Space::with_height(Length::Shrink).into();
+    }
+    let tag_filter = &state.gui.tag_filter;
+    let mut tag_row = row(state.all_tags().into_iter().map(|tag| {
+        button(text(tag))
+            .style(if tag_filter.contains(tag) {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            })
+            .on_press(Message::TagFilterToggled(tag.clone()))
+            .padding([4, 10])
+            .into()
+    }))
+    .spacing(5)
+    .align_items(Alignment::Center);
+    if !tag_filter.is_empty() {
+        tag_row = tag_row.push(Space::with_width(Length::Fill)).push(
+            text_input(
+                "Collection name",
+                &state.gui.input_caches[InputKind::CollectionNameInput],
+            )
+            .on_input(|input| Message::InputChanged(InputKind::CollectionNameInput, input))
+            .on_submit(Message::SaveCollection)
+            .width(Length::Fixed(200.)),
+        );
+        tag_row = tag_row.push(
+            button(text("Save collection"))
+                .on_press(Message::SaveCollection)
+                .padding(8),
+        );
+    }
+    if let Filter::Collection(index) = current_filter {
+        tag_row = tag_row.push(Space::with_width(Length::Fill)).push(
+            button(text("Delete collection"))
+                .style(theme::Button::Destructive)
+                .on_press(Message::RemoveCollection(index))
+                .padding(8),
+        );
+    }
+    tag_row.into()
+}
+fn view_header(style: ViewStyle) -> Element<'static, Message> {
     let title = text(TITLE_NAME)
-        .size(FONT_SIZE_HEADER)
-        .style(FG_COLOR)
+        .size(style.fonts.header)
+        .style(style.muted)
         .horizontal_alignment(alignment::Horizontal::Left);
 
     row![title].into()
 }
-fn view_input(input: &str) -> Element<'static, Message> {
+fn view_input(input: &str, style: ViewStyle) -> Element<'static, Message> {
     text_input("Search", input)
         .id(INPUT_ID.clone())
         .on_input(|input| Message::InputChanged(InputKind::SearchField, input))
         .on_submit(Message::InputSubmit(InputKind::SearchField))
         .padding(15)
-        .size(FONT_SIZE)
+        .size(style.fonts.text)
         .into()
 }
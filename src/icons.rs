@@ -1,6 +1,7 @@
 use iced::{
-    color, theme,
+    theme,
     widget::{svg, Svg},
+    Theme,
 };
 
 pub const ICON_SIZE: f32 = 24.;
@@ -13,15 +14,39 @@ pub enum Icon {
     Unlock,
     Check,
     ArrowLeft,
+    Rewatch,
+    X,
+    Bell,
+    BellOff,
+    Star,
+    Circle,
+    Copy,
+    Edit,
+    Folder,
+    Refresh,
+    Move,
+    Minus,
+    SkipForward,
+    FastForward,
 }
 impl Icon {
+    /// Icon colored like the text of a primary button
     pub fn svg(&self) -> Svg {
+        self.styled(|theme| svg::Appearance {
+            color: Some(theme.extended_palette().primary.strong.text),
+        })
+    }
+    /// Icon colored like plain text, for text buttons and icons outside of buttons
+    pub fn text_svg(&self) -> Svg {
+        self.styled(|theme| svg::Appearance {
+            color: Some(theme.palette().text),
+        })
+    }
+    fn styled(&self, style: fn(&Theme) -> svg::Appearance) -> Svg {
         let handle = svg::Handle::from_memory(self.data());
 
         svg(handle)
-            .style(theme::Svg::custom_fn(|_theme| svg::Appearance {
-                color: Some(color!(0xffffff)),
-            }))
+            .style(theme::Svg::custom_fn(style))
             .width(ICON_SIZE)
             .height(ICON_SIZE)
     }
@@ -34,6 +59,20 @@ impl Icon {
             Icon::Unlock => include_bytes!("../assets/feather/unlock.svg"),
             Icon::Check => include_bytes!("../assets/feather/check.svg"),
             Icon::ArrowLeft => include_bytes!("../assets/feather/arrow-left.svg"),
+            Icon::Rewatch => include_bytes!("../assets/feather/rotate-ccw.svg"),
+            Icon::X => include_bytes!("../assets/feather/x.svg"),
+            Icon::Bell => include_bytes!("../assets/feather/bell.svg"),
+            Icon::BellOff => include_bytes!("../assets/feather/bell-off.svg"),
+            Icon::Star => include_bytes!("../assets/feather/star.svg"),
+            Icon::Circle => include_bytes!("../assets/feather/circle.svg"),
+            Icon::Copy => include_bytes!("../assets/feather/copy.svg"),
+            Icon::Edit => include_bytes!("../assets/feather/edit-2.svg"),
+            Icon::Folder => include_bytes!("../assets/feather/folder.svg"),
+            Icon::Refresh => include_bytes!("../assets/feather/refresh-cw.svg"),
+            Icon::Move => include_bytes!("../assets/feather/move.svg"),
+            Icon::Minus => include_bytes!("../assets/feather/minus.svg"),
+            Icon::SkipForward => include_bytes!("../assets/feather/skip-forward.svg"),
+            Icon::FastForward => include_bytes!("../assets/feather/fast-forward.svg"),
         }
     }
 }
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use chrono::{Days, Utc};
+
+use crate::calendar::UpcomingEpisode;
+use crate::id::EpisodeId;
+
+/// Lines of an iCalendar file should not be longer than 75 octets
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Creates an iCalendar file containing a whole day event for each episode.
+/// The UIDs are derived from the episode, so that calendar apps update instead of duplicate events on reimport
+/// The description starts with the note on the episode, followed by the overview,
+/// the personal rating and the notes on the show
+pub fn to_ics(episodes: &[UpcomingEpisode], notes: &HashMap<EpisodeId, String>) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//Webworm//Upcoming episodes//EN".to_owned(),
+        "CALSCALE:GREGORIAN".to_owned(),
+        "X-WR-CALNAME:Webworm".to_owned(),
+    ];
+    for upcoming in episodes {
+        let episode = &upcoming.episode.episode;
+        let id = EpisodeId(upcoming.bookmark.movie.id, episode.clone().into());
+        let end = upcoming.air_date + Days::new(1);
+        let mut summary = format!(
+            "{} {}x{:02}",
+            upcoming.bookmark.movie.name, episode.season_number, episode.episode_number
+        );
+        if !upcoming.episode.name.is_empty() {
+            summary.push_str(&format!(" {}", upcoming.episode.name));
+        }
+        let rating = upcoming
+            .bookmark
+            .rating
+            .map(|rating| format!("Your rating: {rating}/10"));
+        let description = [
+            notes.get(&id).cloned(),
+            Some(upcoming.episode.overview.clone()),
+            rating,
+            Some(upcoming.bookmark.notes.clone()),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.trim().is_empty())
+        .collect::<Vec<String>>()
+        .join("\n\n");
+        lines.extend([
+            "BEGIN:VEVENT".to_owned(),
+            format!("UID:{}", uid(&id)),
+            format!("DTSTAMP:{stamp}"),
+            format!("DTSTART;VALUE=DATE:{}", upcoming.air_date.format("%Y%m%d")),
+            format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")),
+            format!("SUMMARY:{}", escape(&summary)),
+            format!("DESCRIPTION:{}", escape(&description)),
+            "TRANSP:TRANSPARENT".to_owned(),
+            "END:VEVENT".to_owned(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_owned());
+    lines
+        .iter()
+        .map(|line| fold(line))
+        .collect::<Vec<String>>()
+        .join("")
+}
+/// Stable identifier of the event for the episode
+fn uid(id: &EpisodeId) -> String {
+    format!(
+        "{}-s{}e{}@webworm",
+        id.0.id(),
+        id.1.season(),
+        id.1.episode()
+    )
+}
+/// Escapes the characters which have a special meaning in text values
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+/// Splits a content line into multiple lines of at most 75 octets, terminated by CRLF.
+/// Continuation lines start with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        folded.push(c);
+        line_length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    use super::*;
+    use crate::bookmark::Bookmark;
+    use crate::movie::test_movie;
+    use crate::movie_details::EpisodeDetails;
+
+    fn episode(season_number: usize, episode_number: usize, overview: &str) -> EpisodeDetails {
+        serde_json::from_value(json!({
+            "episode_number": episode_number,
+            "season_number": season_number,
+            "name": "Pilot",
+            "air_date": "2024-03-13",
+            "overview": overview,
+        }))
+        .unwrap()
+    }
+    fn upcoming(bookmark: &Bookmark, episode: EpisodeDetails) -> UpcomingEpisode<'_> {
+        UpcomingEpisode {
+            bookmark,
+            episode,
+            air_date: NaiveDate::from_ymd_opt(2024, 3, 13).unwrap(),
+        }
+    }
+    fn uids(ics: &str) -> Vec<&str> {
+        ics.lines()
+            .filter(|line| line.starts_with("UID:"))
+            .collect()
+    }
+
+    #[test]
+    fn describes_episodes_with_notes_and_rating() {
+        let movie = test_movie(1, "Show");
+        let mut bookmark = Bookmark::from(&movie);
+        bookmark.rating = Some(8);
+        bookmark.notes = "Watch with Sam".to_owned();
+        let upcoming_episode = upcoming(&bookmark, episode(2, 3, "Things happen"));
+        let id = EpisodeId(movie.id, upcoming_episode.episode.episode.clone().into());
+        let notes = HashMap::from([(id, "Stopped at 23:10".to_owned())]);
+        let ics = to_ics(&[upcoming_episode], &notes).replace("\r\n ", "");
+        assert!(ics.contains(
+            "DESCRIPTION:Stopped at 23:10\\n\\nThings happen\\n\\nYour rating: 8/10\\n\\nWatch with Sam\r\n"
+        ));
+        assert!(ics.contains("SUMMARY:Show 2x03 Pilot\r\n"));
+        bookmark.rating = None;
+        bookmark.notes.clear();
+        let ics = to_ics(
+            &[upcoming(&bookmark, episode(2, 3, "Things happen"))],
+            &HashMap::new(),
+        );
+        assert!(ics.contains("DESCRIPTION:Things happen\r\n"));
+    }
+    #[test]
+    fn keeps_the_uids_across_exports() {
+        let mut bookmark = Bookmark::from(&test_movie(7, "Show"));
+        let first = to_ics(
+            &[
+                upcoming(&bookmark, episode(2, 3, "")),
+                upcoming(&bookmark, episode(2, 4, "")),
+            ],
+            &HashMap::new(),
+        );
+        assert_eq!(uids(&first), ["UID:7-s2e3@webworm", "UID:7-s2e4@webworm"]);
+        // Changed names, overviews and notes update the same events
+        bookmark.movie.name = "Renamed".to_owned();
+        bookmark.notes = "Notes".to_owned();
+        let second = to_ics(
+            &[upcoming(&bookmark, episode(2, 4, "Now with an overview"))],
+            &HashMap::new(),
+        );
+        assert_eq!(uids(&second), ["UID:7-s2e4@webworm"]);
+    }
+    #[test]
+    fn folds_lines_at_75_octets() {
+        assert_eq!(fold(&"a".repeat(75)), format!("{}\r\n", "a".repeat(75)));
+        assert_eq!(
+            fold(&"a".repeat(76)),
+            format!("{}\r\n a\r\n", "a".repeat(75))
+        );
+        // Characters are never split, even if the line has to be shorter
+        let line = format!("DESCRIPTION:{}", "é".repeat(60));
+        let folded = fold(&line);
+        for part in folded.split_terminator("\r\n") {
+            assert!(part.len() <= MAX_LINE_LENGTH, "{part:?}");
+        }
+        assert_eq!(folded.replace("\r\n ", ""), format!("{line}\r\n"));
+        let bookmark = Bookmark::from(&test_movie(1, "Show"));
+        let ics = to_ics(
+            &[upcoming(
+                &bookmark,
+                episode(1, 1, &"Long, overview; ".repeat(20)),
+            )],
+            &HashMap::new(),
+        );
+        assert!(ics
+            .split_terminator("\r\n")
+            .all(|line| line.len() <= MAX_LINE_LENGTH));
+    }
+}
@@ -3,7 +3,7 @@ use std::{collections::HashMap, fmt::Display};
 use serde::{Deserialize, Serialize};
 
 use crate::{bookmark::Bookmark, movie::TmdbMovie, movie_details::Episode};
-#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+#[derive(Debug, Clone, PartialEq, Hash, Eq, Serialize, Deserialize)]
 pub struct EpisodeId(pub MovieId, pub Episode);
 
 #[derive(Clone, Hash, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
@@ -0,0 +1,520 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use iced::keyboard::{key::Named, Key, Modifiers};
+use iced::widget::{scrollable, text_input};
+use iced::{widget, window};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::config::BookmarkLayout;
+use crate::filter::Filter;
+use crate::gui::{self, INPUT_ID, SCROLLABLE_ID};
+use crate::id::MovieId;
+use crate::message::{BookmarkMessage, LinkMessage, Message};
+use crate::save;
+use crate::state::State;
+use crate::update::StateUpdate;
+
+/// Something that can be done with a shortcut
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    FocusLeft,
+    FocusRight,
+    FocusUp,
+    FocusDown,
+    OpenDetails,
+    Play,
+    Copy,
+    NextEpisode,
+    PreviousEpisode,
+    FocusSearch,
+    Back,
+    NextWidget,
+    PreviousWidget,
+    Fullscreen,
+    Windowed,
+    ShowKeymap,
+}
+impl Action {
+    pub const ALL: [Action; 16] = [
+        Action::FocusLeft,
+        Action::FocusRight,
+        Action::FocusUp,
+        Action::FocusDown,
+        Action::OpenDetails,
+        Action::Play,
+        Action::Copy,
+        Action::NextEpisode,
+        Action::PreviousEpisode,
+        Action::FocusSearch,
+        Action::Back,
+        Action::NextWidget,
+        Action::PreviousWidget,
+        Action::Fullscreen,
+        Action::Windowed,
+        Action::ShowKeymap,
+    ];
+    fn default_bindings(&self) -> &'static [&'static str] {
+        match self {
+            Action::FocusLeft => &["ArrowLeft"],
+            Action::FocusRight => &["ArrowRight"],
+            Action::FocusUp => &["ArrowUp"],
+            Action::FocusDown => &["ArrowDown"],
+            Action::OpenDetails => &["Enter"],
+            Action::Play => &["p"],
+            Action::Copy => &["c"],
+            Action::NextEpisode => &["+", "="],
+            Action::PreviousEpisode => &["-"],
+            Action::FocusSearch => &["/"],
+            Action::Back => &["Escape"],
+            Action::NextWidget => &["Tab"],
+            Action::PreviousWidget => &["Shift+Tab"],
+            Action::Fullscreen => &["Shift+ArrowUp"],
+            Action::Windowed => &["Shift+ArrowDown"],
+            Action::ShowKeymap => &["?"],
+        }
+    }
+}
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Action::FocusLeft => "Select the bookmark to the left",
+            Action::FocusRight => "Select the bookmark to the right",
+            Action::FocusUp => "Select the bookmark above",
+            Action::FocusDown => "Select the bookmark below",
+            Action::OpenDetails => "Open the details of the selected bookmark",
+            Action::Play => "Play the next episode",
+            Action::Copy => "Copy the link without advancing",
+            Action::NextEpisode => "Next episode",
+            Action::PreviousEpisode => "Previous episode",
+            Action::FocusSearch => "Focus the search",
+            Action::Back => "Go back",
+            Action::NextWidget => "Focus the next input",
+            Action::PreviousWidget => "Focus the previous input",
+            Action::Fullscreen => "Fullscreen",
+            Action::Windowed => "Leave fullscreen",
+            Action::ShowKeymap => "Show the shortcuts",
+        };
+        write!(f, "{description}")
+    }
+}
+/// Key with the modifiers which need to be held
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBinding {
+    key: Key,
+    modifiers: Modifiers,
+}
+impl KeyBinding {
+    /// Parses bindings like `p`, `Ctrl+Enter` or `Shift+ArrowUp`. Names are case insensitive
+    fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let (prefix, key) = if input == "+" {
+            ("", "+")
+        } else if let Some(prefix) = input.strip_suffix("++") {
+            (prefix, "+")
+        } else {
+            input.rsplit_once('+').unwrap_or(("", input))
+        };
+        let mut modifiers = Modifiers::empty();
+        for modifier in prefix.split('+').filter(|modifier| !modifier.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CTRL,
+                "shift" => Modifiers::SHIFT,
+                "alt" => Modifiers::ALT,
+                "logo" | "super" | "cmd" => Modifiers::LOGO,
+                _ => return Err(format!("unknown modifier {modifier} in {input}")),
+            };
+        }
+        if key.chars().count() == 1 {
+            let key = Key::Character(key.to_lowercase().into());
+            return Ok(Self { key, modifiers });
+        }
+        let named = match key.to_lowercase().as_str() {
+            "enter" => Named::Enter,
+            "escape" | "esc" => Named::Escape,
+            "tab" => Named::Tab,
+            "space" => Named::Space,
+            "backspace" => Named::Backspace,
+            "delete" => Named::Delete,
+            "arrowleft" | "left" => Named::ArrowLeft,
+            "arrowright" | "right" => Named::ArrowRight,
+            "arrowup" | "up" => Named::ArrowUp,
+            "arrowdown" | "down" => Named::ArrowDown,
+            "home" => Named::Home,
+            "end" => Named::End,
+            "pageup" => Named::PageUp,
+            "pagedown" => Named::PageDown,
+            _ => return Err(format!("unknown key {key} in {input}")),
+        };
+        Ok(Self {
+            key: Key::Named(named),
+            modifiers,
+        })
+    }
+    /// Characters already include shift, so `?` matches regardless of it
+    fn matches(&self, key: &Key, modifiers: Modifiers) -> bool {
+        match (&self.key, key) {
+            (Key::Character(binding), Key::Character(pressed)) => {
+                let ignored = if self.modifiers.shift() {
+                    Modifiers::empty()
+                } else {
+                    Modifiers::SHIFT
+                };
+                binding.as_str() == pressed.to_lowercase()
+                    && self.modifiers == modifiers.difference(ignored)
+            }
+            (binding, pressed) => binding == pressed && self.modifiers == modifiers,
+        }
+    }
+}
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (Modifiers::CTRL, "Ctrl"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::LOGO, "Logo"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match &self.key {
+            Key::Named(named) => write!(f, "{named:?}"),
+            Key::Character(c) => write!(f, "{c}"),
+            Key::Unidentified => write!(f, "?"),
+        }
+    }
+}
+/// Shortcuts of the actions, the defaults overridden by the keymap file
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyBinding>)>,
+}
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let bindings = action
+                    .default_bindings()
+                    .iter()
+                    .filter_map(|binding| KeyBinding::parse(binding).ok())
+                    .collect();
+                (action, bindings)
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+impl Keymap {
+    pub fn action(&self, key: &Key, modifiers: Modifiers) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, bindings)| bindings.iter().any(|b| b.matches(key, modifiers)))
+            .map(|(action, _)| *action)
+    }
+    /// Actions with their bindings in the order of `Action::ALL`
+    pub fn bindings(&self) -> &[(Action, Vec<KeyBinding>)] {
+        &self.bindings
+    }
+}
+/// Location of the file rebinding the shortcuts, like `play = ["p", "Ctrl+Enter"]`
+pub fn keymap_path() -> PathBuf {
+    let mut path = save::data_path();
+    path.push("keymap.toml");
+    path
+}
+/// Reads the keymap file. Actions missing in the file keep their default bindings
+pub async fn load_keymap() -> Result<Keymap, String> {
+    let path = keymap_path();
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        info!("no keymap at {path:?}, using the default shortcuts");
+        return Ok(Keymap::default());
+    };
+    parse_keymap(&contents)
+}
+/// Parses the contents of a keymap file
+fn parse_keymap(contents: &str) -> Result<Keymap, String> {
+    let file: HashMap<Action, Vec<String>> =
+        toml::from_str(contents).map_err(|e| format!("invalid keymap: {e}"))?;
+    let mut keymap = Keymap::default();
+    for (action, bindings) in &mut keymap.bindings {
+        if let Some(strings) = file.get(action) {
+            *bindings = strings
+                .iter()
+                .map(|binding| KeyBinding::parse(binding))
+                .collect::<Result<_, _>>()?;
+        }
+    }
+    Ok(keymap)
+}
+impl State {
+    /// Moves the selection through the shown bookmarks. Returns the new selection with its
+    /// position relative to all shown bookmarks
+    fn move_focus(&mut self, action: Action) -> Option<(MovieId, f32)> {
+        let sections = self.shown_sections();
+        let total: usize = sections.iter().map(Vec::len).sum();
+        let columns = match self.config.bookmark_layout {
+            BookmarkLayout::Grid => gui::grid_columns(self),
+            BookmarkLayout::List => 1,
+        };
+        let position = self.gui.focused.and_then(|focused| {
+            sections.iter().enumerate().find_map(|(section, ids)| {
+                let index = ids.iter().position(|id| *id == focused)?;
+                Some((section, index))
+            })
+        });
+        let (section, index) = match position {
+            None => (0, 0),
+            Some((section, index)) => match action {
+                Action::FocusLeft if index > 0 => (section, index - 1),
+                Action::FocusLeft if section > 0 => (section - 1, sections[section - 1].len() - 1),
+                Action::FocusRight if index + 1 < sections[section].len() => (section, index + 1),
+                Action::FocusRight if section + 1 < sections.len() => (section + 1, 0),
+                Action::FocusUp if index >= columns => (section, index - columns),
+                Action::FocusUp if section > 0 => {
+                    let above = &sections[section - 1];
+                    let last_row = (above.len() - 1) / columns * columns;
+                    (section - 1, (last_row + index).min(above.len() - 1))
+                }
+                Action::FocusDown if index + columns < sections[section].len() => {
+                    (section, index + columns)
+                }
+                Action::FocusDown if section + 1 < sections.len() => {
+                    (section + 1, index.min(sections[section + 1].len() - 1))
+                }
+                _ => (section, index),
+            },
+        };
+        let id = *sections.get(section)?.get(index)?;
+        self.gui.focused = Some(id);
+        let before: usize = sections[..section].iter().map(Vec::len).sum();
+        let offset = (before + index) as f32 / (total.max(2) - 1) as f32;
+        Some((id, offset))
+    }
+    /// The bookmark which shortcuts act on: the shown one on the details page, otherwise the selected one
+    fn target_bookmark(&self) -> Option<MovieId> {
+        match self.gui.filter {
+            Filter::Details(id) => Some(id),
+            filter if filter.shows_bookmarks() => self.gui.focused,
+            _ => None,
+        }
+    }
+    pub fn key_pressed(&mut self, key: Key, modifiers: Modifiers) -> StateUpdate {
+        let Some(action) = self.keymap.action(&key, modifiers) else {
+            return StateUpdate::default().just_saved();
+        };
+        if self.gui.show_keymap && action != Action::ShowKeymap && action != Action::Back {
+            return StateUpdate::default().just_saved();
+        }
+        let details = |state: &State, id| state.movie_details.get(&id).cloned();
+        match action {
+            Action::FocusLeft | Action::FocusRight | Action::FocusUp | Action::FocusDown => {
+                let Some((_, offset)) = self.move_focus(action) else {
+                    return StateUpdate::default().just_saved();
+                };
+                let offset = scrollable::RelativeOffset { x: 0., y: offset };
+                StateUpdate::new(scrollable::snap_to(SCROLLABLE_ID.clone(), offset)).just_saved()
+            }
+            Action::OpenDetails => match self.gui.filter {
+                filter if filter.shows_bookmarks() => match self.gui.focused {
+                    Some(id) => self.update_state(Message::FilterChanged(Filter::Details(id))),
+                    None => StateUpdate::default().just_saved(),
+                },
+                _ => StateUpdate::default().just_saved(),
+            },
+            Action::Play | Action::Copy => {
+                let Some(id) = self.target_bookmark() else {
+                    return StateUpdate::default().just_saved();
+                };
+                let message = if action == Action::Play {
+                    LinkMessage::ActivateDefault(details(self, id))
+                } else {
+                    LinkMessage::CopyDefault(details(self, id))
+                };
+                self.update_state(Message::LinkMessage(id, message))
+            }
+            Action::NextEpisode | Action::PreviousEpisode => {
+                let Some(id) = self.target_bookmark() else {
+                    return StateUpdate::default().just_saved();
+                };
+                let message = if action == Action::NextEpisode {
+                    BookmarkMessage::IncrE(details(self, id))
+                } else {
+                    BookmarkMessage::DecrE(details(self, id))
+                };
+                self.update_state(Message::BookmarkMessage(id, message))
+            }
+            Action::FocusSearch => {
+                let mut update = StateUpdate::default().just_saved();
+                if !matches!(self.gui.filter, Filter::Details(_)) {
+                    return update.add_command(text_input::focus(INPUT_ID.clone()));
+                }
+                update = self.update_state(Message::FilterChanged(self.gui.previous_filter));
+                update.add_command(text_input::focus(INPUT_ID.clone()))
+            }
+            Action::Back => {
+                if self.gui.show_keymap {
+                    self.gui.show_keymap = false;
+                    StateUpdate::default().just_saved()
+                } else if self.gui.archive_confirmation.is_some() {
+                    self.update_state(Message::ConfirmArchive(false))
+                } else if self.gui.pending_bulk.is_some() {
+                    self.update_state(Message::ConfirmBulk(false))
+                } else if self.gui.selecting {
+                    self.update_state(Message::ToggleSelecting)
+                } else if let Filter::Details(id) = self.gui.filter {
+                    self.gui.focused = Some(id);
+                    self.update_state(Message::FilterChanged(self.gui.previous_filter))
+                } else {
+                    self.gui.focused = None;
+                    StateUpdate::default().just_saved()
+                }
+            }
+            Action::NextWidget => StateUpdate::new(widget::focus_next()).just_saved(),
+            Action::PreviousWidget => StateUpdate::new(widget::focus_previous()).just_saved(),
+            Action::Fullscreen => {
+                self.update_state(Message::ToggleFullscreen(window::Mode::Fullscreen))
+            }
+            Action::Windowed => {
+                self.update_state(Message::ToggleFullscreen(window::Mode::Windowed))
+            }
+            Action::ShowKeymap => {
+                self.gui.show_keymap = !self.gui.show_keymap;
+                StateUpdate::default().just_saved()
+            }
+        }
+    }
+    /// Applies a loaded keymap or keeps the current one if the file is invalid
+    pub fn keymap_loaded(&mut self, keymap: Result<Keymap, String>) {
+        match keymap {
+            Ok(keymap) => {
+                self.keymap = keymap;
+                self.keymap_error = None;
+            }
+            Err(e) => {
+                warn!("{e}");
+                self.keymap_error = Some(e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(key: Key, modifiers: Modifiers) -> KeyBinding {
+        KeyBinding { key, modifiers }
+    }
+    fn character(c: &str) -> Key {
+        Key::Character(c.into())
+    }
+
+    #[test]
+    fn parses_modifier_combinations() {
+        assert_eq!(
+            KeyBinding::parse("Ctrl+Shift+Enter"),
+            Ok(binding(
+                Key::Named(Named::Enter),
+                Modifiers::CTRL | Modifiers::SHIFT
+            ))
+        );
+        assert_eq!(
+            KeyBinding::parse(" alt+super+p "),
+            Ok(binding(character("p"), Modifiers::ALT | Modifiers::LOGO))
+        );
+        assert_eq!(
+            KeyBinding::parse("Control+Cmd+x"),
+            Ok(binding(character("x"), Modifiers::CTRL | Modifiers::LOGO))
+        );
+        // The plus key itself
+        assert_eq!(
+            KeyBinding::parse("+"),
+            Ok(binding(character("+"), Modifiers::empty()))
+        );
+        assert_eq!(
+            KeyBinding::parse("Ctrl++"),
+            Ok(binding(character("+"), Modifiers::CTRL))
+        );
+    }
+    #[test]
+    fn parses_named_keys_in_any_case() {
+        for (input, named) in [
+            ("Enter", Named::Enter),
+            ("esc", Named::Escape),
+            ("ESCAPE", Named::Escape),
+            ("space", Named::Space),
+            ("Left", Named::ArrowLeft),
+            ("arrowdown", Named::ArrowDown),
+            ("PageUp", Named::PageUp),
+        ] {
+            assert_eq!(
+                KeyBinding::parse(input),
+                Ok(binding(Key::Named(named), Modifiers::empty())),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            KeyBinding::parse("SHIFT+P"),
+            Ok(binding(character("p"), Modifiers::SHIFT))
+        );
+    }
+    #[test]
+    fn rejects_invalid_bindings() {
+        for input in ["", "Ctrl+", "Hyper+p", "F13", "Ctrl+Shift+pp", "Enter+p"] {
+            assert!(KeyBinding::parse(input).is_err(), "{input}");
+        }
+    }
+    #[test]
+    fn matches_characters_regardless_of_case_and_shift() {
+        let play = KeyBinding::parse("p").unwrap();
+        assert!(play.matches(&character("p"), Modifiers::empty()));
+        assert!(play.matches(&character("P"), Modifiers::SHIFT));
+        assert!(!play.matches(&character("p"), Modifiers::CTRL));
+        let help = KeyBinding::parse("?").unwrap();
+        assert!(help.matches(&character("?"), Modifiers::SHIFT));
+        let windowed = KeyBinding::parse("Shift+ArrowDown").unwrap();
+        assert!(windowed.matches(&Key::Named(Named::ArrowDown), Modifiers::SHIFT));
+        assert!(!windowed.matches(&Key::Named(Named::ArrowDown), Modifiers::empty()));
+    }
+    #[test]
+    fn keymap_file_overrides_the_defaults() {
+        let keymap = parse_keymap("play = [\"Ctrl+Enter\", \"o\"]\ncopy = []").unwrap();
+        let enter = Key::Named(Named::Enter);
+        assert_eq!(keymap.action(&enter, Modifiers::CTRL), Some(Action::Play));
+        assert_eq!(
+            keymap.action(&character("o"), Modifiers::empty()),
+            Some(Action::Play)
+        );
+        assert_eq!(keymap.action(&character("p"), Modifiers::empty()), None);
+        assert_eq!(keymap.action(&character("c"), Modifiers::empty()), None);
+        // Actions missing in the file keep their defaults
+        assert_eq!(
+            keymap.action(&enter, Modifiers::empty()),
+            Some(Action::OpenDetails)
+        );
+    }
+    #[test]
+    fn invalid_keymap_files_keep_the_defaults() {
+        for contents in [
+            "play = [\"Hyper+p\"]",
+            "play = \"p\"",
+            "dance = [\"d\"]",
+            "play = [",
+        ] {
+            let mut state = State::default();
+            state.keymap_loaded(parse_keymap(contents));
+            assert!(state.keymap_error.is_some(), "{contents}");
+            assert_eq!(
+                state.keymap.action(&character("p"), Modifiers::empty()),
+                Some(Action::Play),
+                "{contents}"
+            );
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Result};
+use tracing::info;
+
+/// Placeholder in command arguments which is replaced by the resolved url
+pub const URL_PLACEHOLDER: &str = "{url}";
+
+/// Starts the program `argv[0]` with the remaining arguments.
+/// The arguments are passed to the program directly and never interpreted by a shell.
+/// The program is waited for in the background so that it doesn't linger as zombie
+pub fn spawn(argv: &[String]) -> Result<()> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| anyhow!("can't run an empty command"))?;
+    info!("launch {program} with {args:?}");
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+/// Replaces the url placeholder in every argument.
+/// If no argument contains the placeholder, the url is appended as last argument
+pub fn with_url(argv: &[String], url: &str) -> Vec<String> {
+    let has_placeholder = argv.iter().any(|arg| arg.contains(URL_PLACEHOLDER));
+    let mut argv: Vec<String> = argv
+        .iter()
+        .map(|arg| arg.replace(URL_PLACEHOLDER, url))
+        .collect();
+    if !argv.is_empty() && !has_placeholder {
+        argv.push(url.to_owned());
+    }
+    argv
+}
+/// Splits a command line into arguments at whitespace.
+/// Double quotes group arguments containing whitespace and `\"` is a literal quote.
+/// No other shell syntax is supported, so other backslashes like in Windows paths stay as they are
+pub fn split_arguments(command: &str) -> Vec<String> {
+    let mut argv = Vec::new();
+    let mut arg = String::new();
+    let mut quoted = false;
+    let mut started = false;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'"') => {
+                arg.push('"');
+                chars.next();
+                started = true;
+            }
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    argv.push(std::mem::take(&mut arg));
+                    started = false;
+                }
+            }
+            c => {
+                arg.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        argv.push(arg);
+    }
+    argv
+}
+/// Joins the arguments to a command line which can be split again by `split_arguments`
+pub fn join_arguments(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            let escaped = arg.replace('"', "\\\"");
+            if !arg.is_empty() && !arg.contains(char::is_whitespace) {
+                return escaped;
+            }
+            // Trailing backslashes go after the closing quote, so they don't escape it
+            let trimmed = escaped.trim_end_matches('\\');
+            format!("\"{trimmed}\"{}", &escaped[trimmed.len()..])
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+/// Command which opens urls with the default application of the system
+pub fn default_opener() -> Vec<String> {
+    let opener: &[&str] = if cfg!(target_os = "windows") {
+        &["explorer"]
+    } else if cfg!(target_os = "macos") {
+        &["open"]
+    } else {
+        &["xdg-open"]
+    };
+    opener.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use super::*;
+
+    fn args(argv: &[&str]) -> Vec<String> {
+        argv.iter().map(|arg| arg.to_string()).collect()
+    }
+    /// Writes a script which saves its arguments after the first one, line by line,
+    /// to the file given as first argument
+    #[cfg(unix)]
+    fn stub_program(name: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut path = std::env::temp_dir();
+        path.push(format!("webworm-stub-{name}-{}", std::process::id()));
+        std::fs::write(
+            &path,
+            "#!/bin/sh\nout=\"$1\"\nshift\nprintf '%s\\n' \"$@\" > \"$out.tmp\"\nmv \"$out.tmp\" \"$out\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+    /// Waits for the stub to write its arguments
+    #[cfg(unix)]
+    fn received_arguments(out: &PathBuf) -> Vec<String> {
+        for _ in 0..100 {
+            if let Ok(contents) = std::fs::read_to_string(out) {
+                let _ = std::fs::remove_file(out);
+                return contents.lines().map(str::to_owned).collect();
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        panic!("the stub program did not run");
+    }
+
+    #[test]
+    fn replaces_the_url_placeholder() {
+        assert_eq!(
+            with_url(&args(&["mpv", "--title={url}", "{url}"]), "https://a.b/c d"),
+            ["mpv", "--title=https://a.b/c d", "https://a.b/c d"]
+        );
+    }
+    #[test]
+    fn appends_the_url_without_placeholder() {
+        assert_eq!(
+            with_url(&args(&["xdg-open"]), "https://a.b"),
+            ["xdg-open", "https://a.b"]
+        );
+        assert!(with_url(&[], "https://a.b").is_empty());
+    }
+    #[test]
+    fn splits_and_joins_arguments() {
+        let argv = split_arguments(r#"mpv --fs "--title=my show" """#);
+        assert_eq!(argv, ["mpv", "--fs", "--title=my show", ""]);
+        assert_eq!(split_arguments(&join_arguments(&argv)), argv);
+    }
+    #[test]
+    fn escapes_quotes() {
+        let argv = split_arguments(r#"mpv --title=\"quoted\" "say \"hi\"" C:\Videos\"#);
+        assert_eq!(
+            argv,
+            ["mpv", "--title=\"quoted\"", "say \"hi\"", "C:\\Videos\\"]
+        );
+        for argv in [
+            args(&["mpv", r#"--title="my show""#, r#"say "hi""#]),
+            args(&[
+                "player",
+                r#"a\"b"#,
+                r#"C:\My Videos\"#,
+                r#"\\server\share"#,
+                "\"",
+            ]),
+            args(&["", " ", r#"""#, r#"\"#, r#"ends with\\"#]),
+        ] {
+            assert_eq!(split_arguments(&join_arguments(&argv)), argv, "{argv:?}");
+        }
+    }
+    #[cfg(unix)]
+    #[test]
+    fn runs_the_program_with_the_url() {
+        let program = stub_program("url");
+        let mut out = std::env::temp_dir();
+        out.push(format!("webworm-stub-url-{}.out", std::process::id()));
+        let argv = args(&[
+            program.to_str().unwrap(),
+            out.to_str().unwrap(),
+            "--title",
+            "episode {url}",
+        ]);
+        spawn(&with_url(&argv, "https://example.com/s1e2")).unwrap();
+        assert_eq!(
+            received_arguments(&out),
+            ["--title", "episode https://example.com/s1e2"]
+        );
+        let _ = std::fs::remove_file(program);
+    }
+    #[test]
+    fn fails_for_missing_programs() {
+        assert!(spawn(&args(&["webworm-no-such-program", "https://a.b"])).is_err());
+        assert!(spawn(&[]).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use iced::Command;
+use tracing::{debug, error, info, warn};
+
+use crate::config::AdvanceMode;
+use crate::id::MovieId;
+use crate::launch;
+use crate::message::Message;
+use crate::movie_details::{Episode, MovieDetails, SeasonEpisode, TotalEpisode};
+use crate::player;
+use crate::state::State;
+
+/// File extensions which are considered to be episodes
+const VIDEO_EXTENSIONS: [&str; 9] = [
+    "mkv", "mp4", "avi", "webm", "m4v", "mov", "wmv", "ts", "flv",
+];
+/// Absolute episode numbers have at most this many digits, longer numbers are most likely years
+const MAX_ABSOLUTE_DIGITS: usize = 3;
+
+/// Episodes found in the local directory of a bookmark
+#[derive(Debug, Clone, Default)]
+pub struct LocalLibrary {
+    pub files: Vec<LocalFile>,
+}
+#[derive(Debug, Clone)]
+pub struct LocalFile {
+    pub episode: Episode,
+    pub path: PathBuf,
+}
+impl LocalLibrary {
+    /// The file of the episode.
+    /// Seasonal and absolute numbering are matched against each other if the details are known
+    pub fn find(&self, episode: &Episode, details: Option<&MovieDetails>) -> Option<&Path> {
+        self.files
+            .iter()
+            .find(|file| match details {
+                Some(details) => {
+                    details.as_total_episodes(&file.episode) == details.as_total_episodes(episode)
+                }
+                None => file.episode == *episode,
+            })
+            .map(|file| file.path.as_path())
+    }
+    /// Seasonal episodes which are available, sorted by season and episode
+    pub fn available(&self, details: Option<&MovieDetails>) -> Vec<SeasonEpisode> {
+        let mut available: Vec<SeasonEpisode> = self
+            .files
+            .iter()
+            .map(|file| match (details, &file.episode) {
+                (_, Episode::Seasonal(episode)) => episode.clone(),
+                (Some(details), episode) => details.as_seasonal_episode(episode),
+                (None, Episode::Total(episode)) => SeasonEpisode {
+                    episode_number: episode.episode,
+                    season_number: 1,
+                },
+            })
+            .collect();
+        available.sort_by_key(|episode| (episode.season_number, episode.episode_number));
+        available.dedup();
+        available
+    }
+}
+/// Searches `dir` and its subdirectories for video files with an episode in their name.
+/// Symlinked directories are followed, but every directory is only searched once.
+/// Unreadable subdirectories are skipped, only an unreadable `dir` is an error
+pub async fn scan(dir: PathBuf) -> Result<LocalLibrary, String> {
+    let entries = std::fs::read_dir(&dir).map_err(|e| {
+        error!("failed to read directory {dir:?}: {e}");
+        format!("Can't read {}: {e}", dir.display())
+    })?;
+    let mut visited: HashSet<PathBuf> = dir.canonicalize().into_iter().collect();
+    let mut files = Vec::new();
+    let mut pending = vec![entries];
+    while let Some(entries) = pending.pop() {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                match path.canonicalize() {
+                    Err(e) => warn!("failed to resolve directory {path:?}: {e}"),
+                    Ok(canonical) if visited.contains(&canonical) => {
+                        debug!("skip {path:?}, it was already searched");
+                    }
+                    Ok(canonical) => {
+                        visited.insert(canonical);
+                        match std::fs::read_dir(&path) {
+                            Ok(entries) => pending.push(entries),
+                            Err(e) => warn!("skip unreadable directory {path:?}: {e}"),
+                        }
+                    }
+                }
+                continue;
+            }
+            let is_video = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    VIDEO_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+                });
+            if !is_video {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            match parse_episode(name) {
+                Some(episode) => files.push(LocalFile { episode, path }),
+                None => debug!("no episode found in {path:?}"),
+            }
+        }
+    }
+    info!("found {} local episodes", files.len());
+    Ok(LocalLibrary { files })
+}
+/// Parses the episode from a file name without extension.
+/// Supports `S01E03`, `1x03` and absolute numbers like `Show - 103` or `[Group] Show [12]`
+pub fn parse_episode(name: &str) -> Option<Episode> {
+    let name = name.to_lowercase();
+    let bytes = name.as_bytes();
+    season_episode(bytes)
+        .or_else(|| cross_episode(bytes))
+        .map(Episode::Seasonal)
+        .or_else(|| absolute_episode(&name).map(Episode::Total))
+}
+/// `S01E03`, `s1.e3` or `S01 E03`
+fn season_episode(bytes: &[u8]) -> Option<SeasonEpisode> {
+    (0..bytes.len())
+        .filter(|&i| bytes[i] == b's' && is_boundary(bytes, i))
+        .find_map(|i| {
+            let (season_number, mut end) = number_at(bytes, i + 1)?;
+            if matches!(bytes.get(end), Some(b' ' | b'.' | b'_' | b'-')) {
+                end += 1;
+            }
+            if bytes.get(end) != Some(&b'e') {
+                return None;
+            }
+            let (episode_number, _) = number_at(bytes, end + 1)?;
+            Some(SeasonEpisode {
+                episode_number,
+                season_number,
+            })
+        })
+}
+/// `1x03`
+fn cross_episode(bytes: &[u8]) -> Option<SeasonEpisode> {
+    (0..bytes.len())
+        .filter(|&i| bytes[i].is_ascii_digit() && is_boundary(bytes, i))
+        .find_map(|i| {
+            let (season_number, end) = number_at(bytes, i)?;
+            if end - i > 2 || bytes.get(end) != Some(&b'x') {
+                return None;
+            }
+            let (episode_number, end) = number_at(bytes, end + 1)?;
+            if bytes.get(end).is_some_and(u8::is_ascii_alphanumeric) {
+                return None;
+            }
+            Some(SeasonEpisode {
+                episode_number,
+                season_number,
+            })
+        })
+}
+/// The last standalone number, which is how releases with absolute numbering name their files.
+/// A version suffix like `03v2` is ignored, numbers of codecs like `h.264`
+/// and audio channels like `5.1` are skipped
+fn absolute_episode(name: &str) -> Option<TotalEpisode> {
+    let bytes = name.as_bytes();
+    // Byte ranges of the alphanumeric words
+    let mut words: Vec<(usize, usize)> = Vec::new();
+    for (i, b) in bytes.iter().enumerate() {
+        if !b.is_ascii_alphanumeric() {
+            continue;
+        }
+        match words.last_mut() {
+            Some((_, end)) if *end == i => *end = i + 1,
+            _ => words.push((i, i + 1)),
+        }
+    }
+    let word = |(start, end): (usize, usize)| &bytes[start..end];
+    // `h.264` or `5.1`, where the dot doesn't separate two parts of the name
+    let joined_by_dot = |(_, end): (usize, usize), (start, _): (usize, usize)| {
+        end + 1 == start && bytes[end] == b'.'
+    };
+    let is_digit = |range| matches!(word(range), [b'0'..=b'9']);
+    (0..words.len()).rev().find_map(|index| {
+        let (start, end) = words[index];
+        let (episode, digits_end) = number_at(bytes, start)?;
+        let no_suffix_or_version = match &bytes[digits_end..end] {
+            [] => true,
+            [b'v', version @ ..] => !version.is_empty() && version.iter().all(u8::is_ascii_digit),
+            _ => false,
+        };
+        if digits_end - start > MAX_ABSOLUTE_DIGITS || !no_suffix_or_version {
+            return None;
+        }
+        let previous = index.checked_sub(1).map(|i| words[i]);
+        let next = words.get(index + 1).copied();
+        let is_codec = previous.is_some_and(|previous| {
+            joined_by_dot(previous, words[index]) && matches!(word(previous), b"h" | b"x")
+        });
+        let is_decimal = is_digit(words[index])
+            && (previous.is_some_and(|previous| {
+                joined_by_dot(previous, words[index]) && is_digit(previous)
+            }) || next.is_some_and(|next| joined_by_dot(words[index], next) && is_digit(next)));
+        (!is_codec && !is_decimal).then_some(TotalEpisode { episode })
+    })
+}
+/// Parses the digits starting at `start`. Returns the number and the index after it
+fn number_at(bytes: &[u8], start: usize) -> Option<(usize, usize)> {
+    let digits = bytes
+        .get(start..)?
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    if digits == 0 {
+        return None;
+    }
+    let number = std::str::from_utf8(&bytes[start..start + digits])
+        .ok()?
+        .parse()
+        .ok()?;
+    Some((number, start + digits))
+}
+/// True if the byte before `index` doesn't continue a word
+fn is_boundary(bytes: &[u8], index: usize) -> bool {
+    index == 0 || !bytes[index - 1].is_ascii_alphanumeric()
+}
+impl State {
+    /// Scans the local directory of the bookmark
+    pub fn scan_library(&self, id: MovieId) -> Command<Message> {
+        let Some(dir) = self
+            .get_bookmark(id)
+            .and_then(|bookmark| bookmark.local_dir.clone())
+        else {
+            return Command::none();
+        };
+        Command::perform(scan(dir), move |library| {
+            Message::LibraryScanned(id, library)
+        })
+    }
+    /// Opens the local file of the active episode in the player.
+    /// Returns `None` if there is no such file, in which case the links should be used instead
+    pub fn play_local(
+        &mut self,
+        id: MovieId,
+        details: Option<MovieDetails>,
+        mode: AdvanceMode,
+    ) -> Option<Command<Message>> {
+        let library = self.libraries.get(&id)?.as_ref().ok()?;
+        let bookmark = self.bookmarks.iter_mut().find(|b| b.movie.id == id)?;
+        let path = library.find(bookmark.active_episode(), details.as_ref())?;
+        let argv = launch::with_url(&self.config.player, &path.to_string_lossy());
+        Some(player::play(&argv, bookmark, details, mode, &self.config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seasonal(season_number: usize, episode_number: usize) -> Option<Episode> {
+        Some(Episode::Seasonal(SeasonEpisode {
+            episode_number,
+            season_number,
+        }))
+    }
+    fn total(episode: usize) -> Option<Episode> {
+        Some(Episode::Total(TotalEpisode { episode }))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scans_symlink_loops_once() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("webworm-library-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let season = dir.join("Season 1");
+        std::fs::create_dir_all(&season).unwrap();
+        std::fs::write(season.join("Show S01E02.mkv"), "").unwrap();
+        std::fs::write(season.join("notes.txt"), "").unwrap();
+        std::os::unix::fs::symlink(&dir, season.join("all")).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("broken")).unwrap();
+        let library = async_std::task::block_on(scan(dir.clone())).unwrap();
+        let episodes: Vec<&Episode> = library.files.iter().map(|file| &file.episode).collect();
+        assert_eq!(episodes, [&seasonal(1, 2).unwrap()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(async_std::task::block_on(scan(dir)).is_err());
+    }
+    #[test]
+    fn parses_episodes_from_file_names() {
+        for (name, episode) in [
+            ("Show.S01E02.1080p.WEB-DL", seasonal(1, 2)),
+            ("show s1.e3", seasonal(1, 3)),
+            ("Show S02 E10 [720p]", seasonal(2, 10)),
+            ("Show (2019) - S03E04v2", seasonal(3, 4)),
+            ("Show 1x03", seasonal(1, 3)),
+            ("Show.2x11.HDTV.x264", seasonal(2, 11)),
+            ("[Group] Show - 03", total(3)),
+            ("[Group] Show - 03 [1080p]", total(3)),
+            ("[Group] Show [12]", total(12)),
+            ("[Group] Show - 03v2 [ABCD1234]", total(3)),
+            ("Show - 103", total(103)),
+            ("Show.05.720p", total(5)),
+            ("Show - 07 (2019)", total(7)),
+            ("Show 2049 - 08 [1920x1080]", total(8)),
+            ("Show - 09 [h.264][AAC 5.1]", total(9)),
+            ("Show - 10 [x.265 2.0]", total(10)),
+        ] {
+            assert_eq!(parse_episode(name), episode, "{name}");
+        }
+    }
+    #[test]
+    fn ignores_numbers_which_are_no_episodes() {
+        for name in [
+            "Show",
+            "Show (2019)",
+            "Show 1080p",
+            "Show [1920x1080]",
+            "Show.2160p.HEVC",
+            "Show h.264",
+            "Show AAC 5.1",
+            "Show 10bit",
+            "Sunday 1999",
+        ] {
+            assert_eq!(parse_episode(name), None, "{name}");
+        }
+    }
+}
@@ -1,69 +1,460 @@
+use std::fmt::Display;
+
 use anyhow::Result;
-use iced::{clipboard, Command};
+use iced::{clipboard, keyboard, Command};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, warn};
 
 use crate::{
     bookmark::Bookmark,
-    message::{BookmarkMessage, Message, ShiftPressed},
+    config::{AdvanceMode, Config},
+    launch,
+    message::Message,
     movie_details::{Episode, MovieDetails},
+    player,
 };
 
-const EPISODE_PLACEHOLDER: &str = "{e}";
-const SEASON_PLACEHOLDER: &str = "{s}";
+const DEFAULT_LINK_NAME: &str = "Stream";
 
+/// A link template like `https://link_to_movie/{slug}/s{s:02}e{e:02}?ep={abs}`.
+///
+/// Placeholders are written as `{name[+offset|-offset][:width]}`.
+/// Literal braces are escaped by doubling them (`{{`, `}}`)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "SavedLink", into = "SavedLink")]
 pub struct Link {
     pub link_parts: Vec<LinkPart>,
     pub string_link: String,
 }
+/// Only the template string is persisted, the parts are parsed again on load
+#[derive(Serialize, Deserialize)]
+struct SavedLink {
+    string_link: String,
+}
+impl From<SavedLink> for Link {
+    fn from(value: SavedLink) -> Self {
+        Link::parse_saved(&value.string_link)
+    }
+}
+impl From<Link> for SavedLink {
+    fn from(value: Link) -> Self {
+        SavedLink {
+            string_link: value.string_link,
+        }
+    }
+}
+/// A link template together with a name, e.g. `Stream` or `Wiki`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedLink {
+    pub name: String,
+    pub link: Link,
+    /// What happens when the link is used
+    #[serde(default)]
+    pub action: LinkAction,
+}
+/// Ways to use a resolved link
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkAction {
+    /// Copy the url to the clipboard
+    #[default]
+    Copy,
+    /// Open the url with the system url handler
+    Open,
+    /// Run a command, `{url}` in the arguments is replaced by the url
+    Command(Vec<String>),
+}
+impl LinkAction {
+    pub const KINDS: [LinkActionKind; 3] = [
+        LinkActionKind::Copy,
+        LinkActionKind::Open,
+        LinkActionKind::Command,
+    ];
+    pub fn kind(&self) -> LinkActionKind {
+        match self {
+            LinkAction::Copy => LinkActionKind::Copy,
+            LinkAction::Open => LinkActionKind::Open,
+            LinkAction::Command(_) => LinkActionKind::Command,
+        }
+    }
+    /// Action forced by held modifiers: ctrl copies, alt opens in the browser
+    pub fn from_modifiers(modifiers: keyboard::Modifiers) -> Option<LinkAction> {
+        if modifiers.control() {
+            Some(LinkAction::Copy)
+        } else if modifiers.alt() {
+            Some(LinkAction::Open)
+        } else {
+            None
+        }
+    }
+}
+/// `LinkAction` without the command, used for selecting the action
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkActionKind {
+    #[default]
+    Copy,
+    Open,
+    Command,
+}
+impl Display for LinkActionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LinkActionKind::Copy => "Copy",
+            LinkActionKind::Open => "Open in browser",
+            LinkActionKind::Command => "Run command",
+        };
+        write!(f, "{name}")
+    }
+}
+/// All links of a bookmark
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "SavedLinks")]
+pub struct BookmarkLinks {
+    pub links: Vec<NamedLink>,
+    /// Index of the link used by the play button
+    pub default: usize,
+}
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedLinks {
+    Named {
+        links: Vec<NamedLink>,
+        #[serde(default)]
+        default: usize,
+    },
+    /// Before named links were introduced every bookmark had a single link
+    Single(Link),
+}
+impl From<SavedLinks> for BookmarkLinks {
+    fn from(value: SavedLinks) -> Self {
+        match value {
+            SavedLinks::Named { links, default } => BookmarkLinks { links, default },
+            SavedLinks::Single(link) => BookmarkLinks {
+                links: vec![NamedLink {
+                    name: DEFAULT_LINK_NAME.into(),
+                    link,
+                    action: LinkAction::Copy,
+                }],
+                default: 0,
+            },
+        }
+    }
+}
+impl BookmarkLinks {
+    /// The link used by the play button.
+    /// Falls back to the first link if the default index is out of range
+    pub fn default_link(&self) -> Option<&NamedLink> {
+        self.links.get(self.default_index())
+    }
+    pub fn default_index(&self) -> usize {
+        if self.default < self.links.len() {
+            self.default
+        } else {
+            0
+        }
+    }
+    /// Inserts the link at `index` or appends it if `index` is `None`
+    pub fn set(&mut self, index: Option<usize>, link: NamedLink) {
+        match index.and_then(|index| self.links.get_mut(index)) {
+            Some(old) => *old = link,
+            None => self.links.push(link),
+        }
+    }
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.links.len() {
+            warn!("tried to remove link {index} which does not exist");
+            return;
+        }
+        self.links.remove(index);
+        if self.default > index || self.default >= self.links.len() {
+            self.default = self.default.saturating_sub(1);
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+    /// Name for a new link if the user didn't give one
+    pub fn unused_name(&self) -> String {
+        if self.is_empty() {
+            DEFAULT_LINK_NAME.into()
+        } else {
+            format!("Link {}", self.links.len() + 1)
+        }
+    }
+}
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LinkPart {
+    Const(String),
+    Placeholder(Placeholder),
+}
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Placeholder {
+    pub variable: Variable,
+    /// Added to the value of the variable
+    pub offset: i64,
+    /// Minimal width, the value is padded with leading zeros
+    pub width: usize,
+}
+/// Values which can be inserted into a link
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Variable {
+    /// `{e}`: Episode in the season.
+    /// If the link has no season, the absolute episode is used instead
+    Episode,
+    /// `{s}`: Season of the episode
+    Season,
+    /// `{abs}`: Episode counted from the start of the show
+    Absolute,
+    /// `{slug}`: Name of the show in lowercase, words separated by `-`
+    Slug,
+}
+impl Variable {
+    fn from_name(name: &str) -> Option<Variable> {
+        match name {
+            "e" => Some(Variable::Episode),
+            "s" => Some(Variable::Season),
+            "abs" => Some(Variable::Absolute),
+            "slug" => Some(Variable::Slug),
+            _ => None,
+        }
+    }
+}
+/// The values of the variables for one episode.
+/// Values are `None` if they can't be computed without the movie details
+#[derive(Debug, Clone, Default)]
+pub struct LinkValues {
+    pub episode: Option<usize>,
+    pub season: Option<usize>,
+    pub absolute: Option<usize>,
+    pub slug: String,
+}
+impl LinkValues {
+    pub fn new(name: &str, episode: &Episode, details: Option<&MovieDetails>) -> Self {
+        let (episode, season, absolute) = match episode {
+            Episode::Seasonal(e) => (
+                Some(e.episode_number),
+                Some(e.season_number),
+                details.map(|details| details.as_total_episodes(e).episode),
+            ),
+            Episode::Total(e) => {
+                let seasonal = details.map(|details| details.as_seasonal_episode(e));
+                (
+                    seasonal.as_ref().map(|e| e.episode_number),
+                    seasonal.as_ref().map(|e| e.season_number),
+                    Some(e.episode),
+                )
+            }
+        };
+        LinkValues {
+            episode,
+            season,
+            absolute,
+            slug: slug(name),
+        }
+    }
+}
+/// Converts the name to lowercase words separated by `-`
+pub fn slug(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
 
-fn parse_link(link: &str) -> Vec<LinkPart> {
+/// Errors of parsing a link template.
+/// Positions are byte offsets into the template
+#[derive(Clone, Debug, PartialEq)]
+pub enum LinkError {
+    NoConstPart,
+    NoEpisode,
+    /// A `{` without a matching `}`
+    UnclosedPlaceholder {
+        position: usize,
+    },
+    /// A `}` without a preceding `{`
+    UnexpectedBrace {
+        position: usize,
+    },
+    UnknownVariable {
+        position: usize,
+        name: String,
+    },
+    InvalidOffset {
+        position: usize,
+    },
+    InvalidWidth {
+        position: usize,
+    },
+}
+impl LinkError {
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            LinkError::NoConstPart | LinkError::NoEpisode => None,
+            LinkError::UnclosedPlaceholder { position }
+            | LinkError::UnexpectedBrace { position }
+            | LinkError::UnknownVariable { position, .. }
+            | LinkError::InvalidOffset { position }
+            | LinkError::InvalidWidth { position } => Some(*position),
+        }
+    }
+}
+impl Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkError::NoConstPart => write!(f, "the link only consists of placeholders"),
+            LinkError::NoEpisode => {
+                write!(f, "the link needs an episode placeholder {{e}} or {{abs}}")
+            }
+            LinkError::UnclosedPlaceholder { position } => {
+                write!(f, "placeholder at {position} is not closed")
+            }
+            LinkError::UnexpectedBrace { position } => write!(
+                f,
+                "unexpected '}}' at {position}, use '}}}}' for a literal brace"
+            ),
+            LinkError::UnknownVariable { position, name } => write!(
+                f,
+                "unknown placeholder '{name}' at {position}, expected e, s, abs or slug"
+            ),
+            LinkError::InvalidOffset { position } => write!(f, "invalid offset at {position}"),
+            LinkError::InvalidWidth { position } => write!(f, "invalid width at {position}"),
+        }
+    }
+}
+impl std::error::Error for LinkError {}
+
+/// Splits the template into constant parts and placeholders.
+/// If `lenient` is set, invalid placeholders and unbalanced braces are kept as literal text
+/// instead of failing, so templates saved by older versions still load
+fn parse_link(link: &str, lenient: bool) -> Result<Vec<LinkPart>, LinkError> {
     let mut parts = Vec::new();
-    parse_link_impl(link, &mut parts);
-    parts
-}
-/// Top-down recursive tokenizer
-fn parse_link_impl(link: &str, parsed: &mut Vec<LinkPart>) {
-    if link.is_empty() {
-        return;
-    }
-    let (part, rest) = match (
-        link.find(EPISODE_PLACEHOLDER),
-        link.find(SEASON_PLACEHOLDER),
-    ) {
-        (None, None) => (LinkPart::Const(link.to_owned()), None),
-        (_, Some(0)) => (LinkPart::Season, Some(&link[SEASON_PLACEHOLDER.len()..])),
-        (Some(0), _) => (LinkPart::Episode, Some(&link[EPISODE_PLACEHOLDER.len()..])),
-        (None, Some(i)) => (LinkPart::Const(link[..i].to_owned()), Some(&link[i..])),
-        (Some(i), None) => (LinkPart::Const(link[..i].to_owned()), Some(&link[i..])),
-        (Some(x), Some(y)) if x > y => (LinkPart::Const(link[..y].to_owned()), Some(&link[y..])),
-        (Some(x), Some(_y)) => (LinkPart::Const(link[..x].to_owned()), Some(&link[x..])),
+    let mut constant = String::new();
+    let mut chars = link.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '{' if chars.peek().is_some_and(|(_, next)| *next == '{') => {
+                chars.next();
+                constant.push('{');
+            }
+            '}' if chars.peek().is_some_and(|(_, next)| *next == '}') => {
+                chars.next();
+                constant.push('}');
+            }
+            '}' if lenient => constant.push('}'),
+            '}' => return Err(LinkError::UnexpectedBrace { position }),
+            '{' => {
+                let parsed = link[position..]
+                    .find('}')
+                    .ok_or(LinkError::UnclosedPlaceholder { position })
+                    .and_then(|length| {
+                        let content = &link[position + 1..position + length];
+                        Ok((length, parse_placeholder(content, position + 1)?))
+                    });
+                let (length, placeholder) = match parsed {
+                    Ok(parsed) => parsed,
+                    Err(_) if lenient => {
+                        constant.push('{');
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                };
+                if !constant.is_empty() {
+                    parts.push(LinkPart::Const(std::mem::take(&mut constant)));
+                }
+                parts.push(LinkPart::Placeholder(placeholder));
+                // skip the placeholder content including the closing brace
+                while chars.next_if(|(i, _)| *i <= position + length).is_some() {}
+            }
+            c => constant.push(c),
+        }
+    }
+    if !constant.is_empty() {
+        parts.push(LinkPart::Const(constant));
+    }
+    Ok(parts)
+}
+/// Parses the content of a placeholder, e.g. `e+12:02`.
+/// `start` is the position of the content in the whole link
+fn parse_placeholder(content: &str, start: usize) -> Result<Placeholder, LinkError> {
+    let (expression, width) = match content.split_once(':') {
+        Some((expression, width)) => {
+            let position = start + expression.len() + 1;
+            let width = width
+                .parse::<usize>()
+                .ok()
+                .filter(|_| !width.starts_with('+'))
+                .ok_or(LinkError::InvalidWidth { position })?;
+            (expression, width)
+        }
+        None => (content, 0),
+    };
+    let (name, offset) = match expression.find(['+', '-']) {
+        Some(i) => {
+            let position = start + i;
+            let offset = expression[i + 1..]
+                .parse::<i64>()
+                .ok()
+                .filter(|_| !expression[i + 1..].starts_with(['+', '-']))
+                .ok_or(LinkError::InvalidOffset { position })?;
+            let offset = if expression[i..].starts_with('-') {
+                -offset
+            } else {
+                offset
+            };
+            (&expression[..i], offset)
+        }
+        None => (expression, 0),
     };
-    parsed.push(part);
-    if let Some(rest) = rest {
-        parse_link_impl(rest, parsed);
+    let name = name.trim();
+    let variable = Variable::from_name(name).ok_or_else(|| LinkError::UnknownVariable {
+        position: start,
+        name: name.to_owned(),
+    })?;
+    if variable == Variable::Slug && (offset != 0 || width != 0) {
+        return Err(LinkError::InvalidOffset { position: start });
     }
+    Ok(Placeholder {
+        variable,
+        offset,
+        width,
+    })
 }
 impl Link {
-    pub fn url(&self, episode: usize, season: usize) -> String {
-        self.link_parts
-            .clone()
-            .into_iter()
-            .map(|part| match part {
-                LinkPart::Const(s) => s.clone(),
-                LinkPart::Episode => episode.to_string(),
-                LinkPart::Season => season.to_string(),
-            })
-            .collect()
+    /// Fills the placeholders with the values.
+    /// Returns `None` if a value is needed that is not known
+    pub fn url(&self, values: &LinkValues) -> Option<String> {
+        let has_season = self.has_season();
+        let mut url = String::new();
+        for part in &self.link_parts {
+            let placeholder = match part {
+                LinkPart::Const(s) => {
+                    url.push_str(s);
+                    continue;
+                }
+                LinkPart::Placeholder(placeholder) => placeholder,
+            };
+            let value = match placeholder.variable {
+                Variable::Slug => {
+                    url.push_str(&values.slug);
+                    continue;
+                }
+                Variable::Episode if has_season => values.episode?,
+                Variable::Episode | Variable::Absolute => values.absolute?,
+                Variable::Season => values.season?,
+            };
+            let value = (value as i64 + placeholder.offset).max(0);
+            url.push_str(&format!("{value:0width$}", width = placeholder.width));
+        }
+        Some(url)
     }
     pub fn has_season(&self) -> bool {
-        self.link_parts.iter().any(|p| *p == LinkPart::Season)
+        self.link_parts.iter().any(|part| {
+            matches!(part, LinkPart::Placeholder(placeholder) if placeholder.variable == Variable::Season)
+        })
     }
-    /// Creates a new Bookmark link.
-    /// Panics if the link is not valid
+    /// Creates a new Bookmark link from a template.
     pub fn new(link: &str) -> Result<Link, LinkError> {
-        let link_parts = parse_link(link);
+        let link_parts = parse_link(link, false)?;
 
         Link::is_valid_link(&link_parts)?;
         Ok(Link {
@@ -71,93 +462,192 @@ impl Link {
             string_link: link.into(),
         })
     }
+    /// Creates a link from a saved template without rejecting it.
+    /// Anything which is not a valid placeholder is kept as literal text
+    fn parse_saved(link: &str) -> Link {
+        let link_parts = match parse_link(link, true) {
+            Ok(link_parts) => link_parts,
+            Err(e) => {
+                error!("failed to parse saved link {link}: {e}");
+                vec![LinkPart::Const(link.into())]
+            }
+        };
+        Link {
+            link_parts,
+            string_link: link.into(),
+        }
+    }
 
     /// A link is valid if it contains:
     /// * at least one const part
-    /// * exactly one episode
-    /// * a maximum of one season
+    /// * at least one episode, either `{e}` or `{abs}`
     fn is_valid_link(link: &[LinkPart]) -> Result<(), LinkError> {
-        let mut consts = 0;
-        let mut episode = 0;
-        let mut season = 0;
-        for part in link {
-            match part {
-                LinkPart::Const(_) => consts += 1,
-                LinkPart::Episode => episode += 1,
-                LinkPart::Season => season += 1,
-            }
-        }
-        if consts == 0 {
+        let has_const = link.iter().any(|part| matches!(part, LinkPart::Const(_)));
+        let has_episode = link.iter().any(|part| {
+            matches!(
+                part,
+                LinkPart::Placeholder(Placeholder {
+                    variable: Variable::Episode | Variable::Absolute,
+                    ..
+                })
+            )
+        });
+        if !has_const {
             warn!("a bookmark link should always include a const part");
             Err(LinkError::NoConstPart)
-        } else if episode == 0 {
-            warn!("a bookmark link should always include one episode");
+        } else if !has_episode {
+            warn!("a bookmark link should always include an episode");
             Err(LinkError::NoEpisode)
-        } else if episode > 1 {
-            warn!("a bookmark link should always include one episode");
-            Err(LinkError::ToManyEpisodes)
-        } else if season > 1 {
-            warn!("a bookmark link shouldn't include more than one season");
-            Err(LinkError::ToManySeasons)
         } else {
             Ok(())
         }
     }
 }
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub enum LinkPart {
-    Const(String),
-    Episode,
-    Season,
-}
-#[derive(Clone, Debug, PartialEq)]
-pub enum LinkError {
-    NoConstPart,
-    NoEpisode,
-    ToManySeasons,
-    ToManyEpisodes,
-}
-impl Link {
-    pub fn to_clipboard(
-        &mut self,
+impl NamedLink {
+    /// Resolves the link for the active episode of the bookmark and applies the action to it.
+    /// The bookmark is advanced to the next episode according to `mode`,
+    /// players which report their progress advance it once the episode was watched
+    pub fn activate(
+        &self,
+        action: &LinkAction,
         bookmark: &mut Bookmark,
         details: Option<MovieDetails>,
-        shift: ShiftPressed,
+        mode: AdvanceMode,
+        config: &Config,
     ) -> Command<Message> {
-        let url = match &bookmark.current_episode {
-            Episode::Seasonal(e) => {
-                if self.has_season() {
-                    self.url(e.episode_number, e.season_number)
-                } else {
-                    let Some(details) = &details else {
-                        error!("load details before copying to clipboard");
-                        return Command::none();
-                    };
-                    self.url(details.as_total_episodes(e).episode, 1)
-                }
+        let values = LinkValues::new(
+            &bookmark.movie.name,
+            bookmark.active_episode(),
+            details.as_ref(),
+        );
+        let Some(url) = self.link.url(&values) else {
+            error!("load details before using the link");
+            return Command::none();
+        };
+        match action {
+            LinkAction::Copy => {
+                debug!("copied {} to clipboard", &url);
+                Command::batch([
+                    player::advance(bookmark, details, mode),
+                    clipboard::write::<Message>(url),
+                ])
             }
-            Episode::Total(e) => {
-                if self.has_season() {
-                    let Some(details) = &details else {
-                        error!("load details before copying to clipboard");
-                        return Command::none();
-                    };
-                    let e = details.as_seasonal_episode(e);
-                    self.url(e.episode_number, e.season_number)
-                } else {
-                    self.url(e.episode, 1)
-                }
+            LinkAction::Open => {
+                let argv = launch::with_url(&config.url_opener, &url);
+                player::play(&argv, bookmark, details, mode, config)
             }
+            LinkAction::Command(argv) => {
+                let argv = launch::with_url(argv, &url);
+                player::play(&argv, bookmark, details, mode, config)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholder(variable: Variable, offset: i64, width: usize) -> LinkPart {
+        LinkPart::Placeholder(Placeholder {
+            variable,
+            offset,
+            width,
+        })
+    }
+    fn constant(text: &str) -> LinkPart {
+        LinkPart::Const(text.into())
+    }
+
+    #[test]
+    fn parses_episode_and_season() {
+        assert_eq!(
+            parse_link("https://example.com/s{s}e{e}", false),
+            Ok(vec![
+                constant("https://example.com/s"),
+                placeholder(Variable::Season, 0, 0),
+                constant("e"),
+                placeholder(Variable::Episode, 0, 0),
+            ])
+        );
+    }
+    #[test]
+    fn parses_padding_and_offset() {
+        assert_eq!(
+            parse_link("ep/{e:02}/{abs+12:3}/{s-1}", false),
+            Ok(vec![
+                constant("ep/"),
+                placeholder(Variable::Episode, 0, 2),
+                constant("/"),
+                placeholder(Variable::Absolute, 12, 3),
+                constant("/"),
+                placeholder(Variable::Season, -1, 0),
+            ])
+        );
+        let link = Link::new("ep/s{s:02}e{e:02}").unwrap();
+        let values = LinkValues {
+            episode: Some(3),
+            season: Some(1),
+            ..LinkValues::default()
         };
-        debug!("copied {} to clipboard", &url);
-        if shift == ShiftPressed::True {
-            debug!("Since shift was pressed the bookmark is not increased");
-            Command::batch([clipboard::write::<Message>(url)])
-        } else {
-            Command::batch([
-                bookmark.apply(BookmarkMessage::IncrE(details)),
-                clipboard::write::<Message>(url),
+        assert_eq!(link.url(&values).as_deref(), Some("ep/s01e03"));
+    }
+    #[test]
+    fn parses_escaped_braces() {
+        assert_eq!(
+            parse_link("a{{b}}{e}", false),
+            Ok(vec![constant("a{b}"), placeholder(Variable::Episode, 0, 0)])
+        );
+    }
+    #[test]
+    fn rejects_unknown_placeholders() {
+        assert_eq!(
+            parse_link("ep/{x}/{e}", false),
+            Err(LinkError::UnknownVariable {
+                position: 4,
+                name: "x".into()
+            })
+        );
+        assert_eq!(
+            parse_link("ep/{e:x}", false),
+            Err(LinkError::InvalidWidth { position: 6 })
+        );
+    }
+    #[test]
+    fn rejects_unbalanced_braces() {
+        assert_eq!(
+            parse_link("ep/{e", false),
+            Err(LinkError::UnclosedPlaceholder { position: 3 })
+        );
+        assert_eq!(
+            parse_link("ep/}{e}", false),
+            Err(LinkError::UnexpectedBrace { position: 3 })
+        );
+    }
+    #[test]
+    fn keeps_invalid_placeholders_as_text_when_lenient() {
+        assert_eq!(
+            parse_link("ep/{x}/{e}", true),
+            Ok(vec![
+                constant("ep/{x}/"),
+                placeholder(Variable::Episode, 0, 0)
             ])
-        }
+        );
+        assert_eq!(parse_link("ep/}{e", true), Ok(vec![constant("ep/}{e")]));
+    }
+    #[test]
+    fn loads_saved_links_with_unknown_placeholders() {
+        let link: Link =
+            serde_json::from_str(r#"{"string_link":"https://example.com/{id}?ep={e}"}"#).unwrap();
+        assert_eq!(link.string_link, "https://example.com/{id}?ep={e}");
+        let values = LinkValues {
+            absolute: Some(7),
+            ..LinkValues::default()
+        };
+        assert_eq!(
+            link.url(&values).as_deref(),
+            Some("https://example.com/{id}?ep=7")
+        );
+        assert!(Link::new("https://example.com/{id}?ep={e}").is_err());
     }
 }
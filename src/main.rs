@@ -1,25 +1,42 @@
 #![windows_subsystem = "windows"]
 
+pub mod archive;
 pub mod bookmark;
+pub mod calendar;
+pub mod chart;
+pub mod collection;
+pub mod config;
 pub mod filter;
 pub mod gui;
 pub mod icons;
+pub mod ics;
 pub mod id;
+pub mod keymap;
+pub mod launch;
+pub mod library;
 pub mod link;
 pub mod message;
 pub mod movie;
 pub mod movie_details;
+pub mod notify;
+pub mod player;
 pub mod response;
 pub mod save;
+pub mod search;
+pub mod selection;
+pub mod sort;
 pub mod state;
+pub mod stats;
+pub mod theme;
 pub mod tmdb;
 pub mod update;
 pub mod view;
 use anyhow::Result;
 use iced::{window, Application, Settings, Size};
 
+use crate::config::Config;
 use crate::gui::App;
-use tracing::{level_filters::LevelFilter, Level};
+use tracing::{error, level_filters::LevelFilter, Level};
 use tracing_subscriber::{filter::FilterFn, layer::SubscriberExt, util::SubscriberInitExt};
 
 const LOG_IGNORE: [&str; 13] = [
@@ -67,9 +84,14 @@ fn main() -> Result<()> {
         .with(filter)
         .with(fmt_layer)
         .init();
+    let config = Config::load()
+        .inspect_err(|e| error!("failed to load the config: {e}"))
+        .ok()
+        .flatten()
+        .unwrap_or_default();
     App::run(Settings {
         window: window::Settings {
-            size: Size::new(800.0, 500.0),
+            size: Size::new(config.window_width, config.window_height),
             ..window::Settings::default()
         },
         ..Settings::default()
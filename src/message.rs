@@ -1,13 +1,25 @@
+use std::path::PathBuf;
+
 use iced::widget::image::Handle;
-use iced::widget::{container, text};
-use iced::{alignment, window, Color, Element, Length};
+use iced::widget::{container, text, text_editor};
+use iced::{alignment, keyboard, window, Element, Length};
 
+use crate::bookmark::WatchStatus;
+use crate::calendar::CalendarRange;
+use crate::config::{AdvanceMode, BookmarkLayout, QuietHours, Setting};
 use crate::filter::Filter;
-use crate::id::MovieId;
-use crate::movie_details::MovieDetails;
+use crate::id::{EpisodeId, MovieId};
+use crate::keymap::Keymap;
+use crate::library::LocalLibrary;
+use crate::link::LinkActionKind;
+use crate::movie_details::{Episode, MovieDetails};
 use crate::save::{LoadError, SaveError, SavedState};
+use crate::selection::BulkAction;
+use crate::sort::SortOrder;
 use crate::state::InputKind;
-use crate::tmdb::RequestType;
+use crate::stats::StatsRange;
+use crate::theme::{CustomTheme, ThemeChoice, ViewStyle};
+use crate::tmdb::{CachedResponse, RequestType};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -21,13 +33,97 @@ pub enum Message {
     ExecuteRequest(RequestType),
     RequestResponse(Option<String>, RequestType),
     RequestPoster(MovieId, Option<Handle>),
+    /// Periodic timer to refresh the details of the bookmarks
+    RefreshTick,
+    Refreshed(MovieId, Option<CachedResponse>),
     FilterChanged(Filter),
+    CalendarRangeChanged(CalendarRange),
+    ExportCalendar,
+    Notified(Result<(), String>),
+    /// Periodic check whether held back notifications can be sent
+    DeliverHeldBack,
+    ClearNotifications,
+    QuietHoursChanged(Option<QuietHours>),
+    CalendarExported(Result<PathBuf, SaveError>),
+    CalendarSubscriptionWritten(Result<PathBuf, SaveError>),
+    StatsRangeChanged(StatsRange),
+    /// Writes the statistics as JSON to the data directory
+    ExportStats,
+    StatsExported(Result<PathBuf, SaveError>),
+    /// Scans the local directory of the bookmark again
+    ScanLibrary(MovieId),
+    LibraryScanned(MovieId, Result<LocalLibrary, String>),
+    /// A tracked player was closed with the episode played up to the percentage
+    PlaybackEnded(MovieId, Episode, Result<f64, String>),
+    /// Asks whether the episode of the bookmark was watched
+    AskAdvance(MovieId, Episode),
+    /// Answer to the question whether the asked episode of the bookmark was watched
+    AnswerAdvance(MovieId, bool),
+    AdvanceModeChanged(AdvanceMode),
+    /// Input of a setting changed, it is applied if it is valid
+    SettingChanged(Setting, String),
+    TrackPlayerProgressToggled(bool),
+    WindowResized(f32),
+    CardWidthChanged(f32),
+    BookmarkLayoutChanged(BookmarkLayout),
+    SortChanged(SortOrder),
+    /// Starts dragging a bookmark to change the manual order
+    DragStart(MovieId),
+    /// The dragged bookmark entered the bookmark with the id
+    DragOver(MovieId),
+    DragEnd,
+    /// Shows only bookmarks with the tag in addition to the already selected tags
+    TagFilterToggled(String),
+    /// Saves the selected tags as collection
+    SaveCollection,
+    RemoveCollection(usize),
+    /// Edits the notes of the bookmark shown in the details
+    NotesEdited(text_editor::Action),
+    /// Sets the note of the episode, an empty note removes it
+    EpisodeNoteChanged(EpisodeId, String),
+    ThemeChanged(ThemeChoice),
+    /// Reads the palettes in the themes directory again
+    ReloadThemes,
+    ThemesLoaded(Vec<CustomTheme>),
+    /// Periodic check whether the system switched between light and dark mode
+    DetectSystemTheme,
+    SystemThemeDetected(bool),
     AddBookmark(MovieId),
+    /// Asks whether the bookmark should be moved to the archive
     RemoveBookmark(MovieId),
+    /// Answer to the question whether to archive or purge
+    ConfirmArchive(bool),
+    RestoreBookmark(MovieId),
+    /// Asks whether the archived bookmark should be deleted permanently
+    PurgeBookmark(MovieId),
+    PurgeArchive,
+    /// Number of deleted poster files of purged bookmarks
+    PostersRemoved(usize),
     BookmarkMessage(MovieId, BookmarkMessage),
+    /// Sets the status of all bookmarks shown in the current filter
+    BulkStatus(WatchStatus),
+    /// Enters or leaves the selection mode
+    ToggleSelecting,
+    /// Selects or deselects the bookmark, with shift a range of bookmarks
+    ToggleSelected(MovieId),
+    SelectAllShown,
+    ClearSelection,
+    /// Applies the action to the selected bookmarks, asking first if it is destructive
+    Bulk(BulkAction),
+    ConfirmBulk(bool),
+    /// Reverts the last bulk action
+    Undo,
+    DismissUndo,
+    BookmarksExported(Result<PathBuf, SaveError>),
+    DismissNewEpisodes,
     LinkMessage(MovieId, LinkMessage),
-    TabPressed,
-    ShiftPressed(ShiftPressed),
+    KeyPressed(keyboard::Key, keyboard::Modifiers),
+    /// Shows or hides the list of shortcuts
+    ToggleKeymap,
+    /// Reads the keymap file again
+    ReloadKeymap,
+    KeymapLoaded(Result<Keymap, String>),
+    ModifiersChanged(keyboard::Modifiers),
     ToggleFullscreen(window::Mode),
 }
 pub fn loading_message<'a>() -> Element<'a, Message> {
@@ -41,13 +137,13 @@ pub fn loading_message<'a>() -> Element<'a, Message> {
     .center_y()
     .into()
 }
-pub fn empty_message(message: &str) -> Element<'_, Message> {
+pub fn empty_message(message: &str, style: ViewStyle) -> Element<'_, Message> {
     container(
         text(message)
             .width(Length::Fill)
             .size(25)
             .horizontal_alignment(alignment::Horizontal::Center)
-            .style(Color::from([0.7, 0.7, 0.7])),
+            .style(style.muted),
     )
     .height(200)
     .center_y()
@@ -59,16 +155,31 @@ pub enum BookmarkMessage {
     DecrE(Option<MovieDetails>),
     SetE(String, Option<MovieDetails>),
     SetS(String, Option<MovieDetails>),
+    /// Skips the rest of the current season
+    FinishSeason(Option<MovieDetails>),
+    /// Continues with the last published episode
+    JumpToLatest(Option<MovieDetails>),
     ToggleSync,
-}
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
-pub enum ShiftPressed {
-    True,
-    #[default]
-    False,
-    Unknown,
+    StartRewatch,
+    StopRewatch,
+    SetStatus(WatchStatus),
+    ToggleMute,
+    AddTag(String),
+    RemoveTag(String),
+    /// Personal rating from 1 to 10, `None` removes the rating
+    SetRating(Option<u8>),
 }
 #[derive(Clone, Debug)]
 pub enum LinkMessage {
-    LinkToClipboard(Option<MovieDetails>, ShiftPressed),
+    /// Uses the default link of the bookmark
+    ActivateDefault(Option<MovieDetails>),
+    /// Uses the link at the index
+    Activate(usize, Option<MovieDetails>),
+    /// Copies the default link without advancing the bookmark
+    CopyDefault(Option<MovieDetails>),
+    /// Selects the action of the link in the edit form
+    ActionKindChanged(LinkActionKind),
+    SetDefault(usize),
+    Edit(usize),
+    Remove(usize),
 }
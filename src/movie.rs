@@ -18,8 +18,20 @@ impl TmdbMovie {
     pub fn rating(&self) -> u8 {
         (self.vote_average * 10.) as u8
     }
-    pub fn matches_filter(&self, filter: &str) -> bool {
-        self.name.to_lowercase().contains(filter)
-            || self.original_name.to_lowercase().contains(filter)
-    }
+}
+
+/// Search result of a show with the id and name, for tests
+#[cfg(test)]
+pub(crate) fn test_movie(id: usize, name: &str) -> TmdbMovie {
+    serde_json::from_value(serde_json::json!({
+        "id": id,
+        "genre_ids": [],
+        "overview": "",
+        "vote_average": 8.0,
+        "original_name": name,
+        "name": name,
+        "popularity": 1.0,
+        "poster_path": null,
+    }))
+    .unwrap()
 }
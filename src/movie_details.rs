@@ -1,5 +1,6 @@
-use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use chrono::NaiveDate;
+use serde::{Deserialize, Deserializer, Serialize};
+use tracing::{error, info, warn};
 
 use crate::id::MovieId;
 
@@ -13,6 +14,9 @@ pub struct MovieDetails {
     number_of_episodes: usize,
     last_episode_to_air: Option<EpisodeDetails>,
     next_episode_to_air: Option<EpisodeDetails>,
+    /// Typical runtimes of the episodes in minutes
+    #[serde(default)]
+    episode_run_time: Vec<usize>,
     #[serde(skip)]
     fixed: bool,
 }
@@ -216,16 +220,107 @@ impl MovieDetails {
         }
         episode
     }
+    /// First episode of the season after the one containing `episode`, if it is published already
+    pub fn next_season_start(&self, episode: &Episode) -> Option<SeasonEpisode> {
+        let last_published = self.last_published()?;
+        let next = SeasonEpisode {
+            episode_number: 1,
+            season_number: self.as_seasonal_episode(episode).season_number + 1,
+        };
+        let exists = self
+            .seasons
+            .iter()
+            .any(|season| season.season_number == next.season_number);
+        let published = self.as_total_episodes(&next).episode
+            <= self.as_total_episodes(&last_published.episode).episode;
+        (exists && published).then_some(next)
+    }
+    /// Converts the episode to the numbering used by `like`
+    pub fn in_format_of(&self, episode: SeasonEpisode, like: &Episode) -> Episode {
+        match like {
+            Episode::Seasonal(_) => episode.into(),
+            Episode::Total(_) => self.as_total_episodes(&episode).into(),
+        }
+    }
     /// Tries to fetch the last published episode.
     /// In case the last episode was not given, the `id` from the movie is used
     pub fn last_published(&self) -> Option<EpisodeDetails> {
         self.last_episode_to_air.clone()
     }
+    /// Counts the published episodes which come after the given progress.
+    /// The `current` episode itself is only counted as watched if `finished` is set
+    pub fn episodes_behind(&self, current: &Episode, finished: bool) -> usize {
+        let Some(last_published) = self.last_published() else {
+            return 0;
+        };
+        let published = self.as_total_episodes(&last_published.episode).episode;
+        let current = self.as_total_episodes(current).episode;
+        let watched = if finished {
+            current
+        } else {
+            current.saturating_sub(1)
+        };
+        published.saturating_sub(watched)
+    }
+    /// Average of the typical episode runtimes in minutes
+    pub fn episode_runtime(&self) -> Option<usize> {
+        let count = self.episode_run_time.len();
+        (count > 0).then(|| self.episode_run_time.iter().sum::<usize>() / count)
+    }
     pub fn next_episode_to_air(&self) -> Option<EpisodeDetails> {
         self.next_episode_to_air.clone()
     }
 }
 
+/// Details of a show with seasons of the given lengths, for tests.
+/// `last_aired` is the (season, episode) of the last published episode
+#[cfg(test)]
+pub(crate) fn test_details(
+    id: usize,
+    episode_counts: &[usize],
+    last_aired: (usize, usize),
+    next_air_date: Option<NaiveDate>,
+) -> MovieDetails {
+    use serde_json::json;
+
+    let episode = |(season_number, episode_number), air_date: Option<NaiveDate>| {
+        json!({
+            "episode_number": episode_number,
+            "season_number": season_number,
+            "name": "",
+            "air_date": air_date.map(|date| date.to_string()),
+            "overview": "",
+        })
+    };
+    let seasons: Vec<_> = episode_counts
+        .iter()
+        .enumerate()
+        .map(|(i, count)| {
+            json!({
+                "id": i + 1,
+                "name": format!("Season {}", i + 1),
+                "episode_count": count,
+                "season_number": i + 1,
+                "overview": "",
+                "poster_path": null,
+            })
+        })
+        .collect();
+    let next_episode =
+        next_air_date.map(|date| episode((last_aired.0, last_aired.1 + 1), Some(date)));
+    serde_json::from_value(json!({
+        "id": id,
+        "seasons": seasons,
+        "in_production": true,
+        "last_air_date": null,
+        "number_of_seasons": episode_counts.len(),
+        "number_of_episodes": episode_counts.iter().sum::<usize>(),
+        "last_episode_to_air": episode(last_aired, None),
+        "next_episode_to_air": next_episode,
+    }))
+    .unwrap()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Season {
     id: usize,
@@ -246,8 +341,36 @@ pub struct EpisodeDetails {
     #[serde(flatten)]
     pub episode: SeasonEpisode,
     pub name: String,
-    pub air_date: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_air_date")]
+    pub air_date: Option<NaiveDate>,
     pub overview: String,
+    /// Runtime in minutes
+    #[serde(default)]
+    pub runtime: Option<usize>,
+}
+/// Tmdb sends empty strings for unknown air dates, which are treated as missing.
+/// Dates in other formats are ignored as well, so that they don't fail the whole response
+fn deserialize_air_date<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let date: Option<String> = Option::deserialize(deserializer)?;
+    match date.as_deref() {
+        None | Some("") => Ok(None),
+        Some(date) => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => Ok(Some(date)),
+            Err(e) => {
+                warn!("ignoring the air date {date:?}: {e}");
+                Ok(None)
+            }
+        },
+    }
+}
+/// Details of a season including all of its episodes
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct SeasonDetails {
+    pub season_number: usize,
+    pub episodes: Vec<EpisodeDetails>,
 }
 #[derive(Eq, Debug, PartialEq, Serialize, Hash, Deserialize, Clone)]
 pub enum Episode {
@@ -342,3 +465,85 @@ impl SeasonEpisode {
         format!("{}E · {}S", self.episode_number, self.season_number)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn air_date(date: serde_json::Value) -> Option<NaiveDate> {
+        let episode: EpisodeDetails = serde_json::from_value(json!({
+            "episode_number": 1,
+            "season_number": 1,
+            "name": "",
+            "air_date": date,
+            "overview": "",
+        }))
+        .unwrap();
+        episode.air_date
+    }
+
+    #[test]
+    fn parses_air_dates_leniently() {
+        assert_eq!(
+            air_date(json!("2024-03-13")),
+            NaiveDate::from_ymd_opt(2024, 3, 13)
+        );
+        for date in [
+            json!(""),
+            json!(null),
+            json!("2024"),
+            json!("13.03.2024"),
+            json!("2024-02-30"),
+        ] {
+            assert_eq!(air_date(date.clone()), None, "{date}");
+        }
+        // A missing date is fine as well, but other types are still an error
+        let missing: EpisodeDetails = serde_json::from_value(json!({
+            "episode_number": 1,
+            "season_number": 1,
+            "name": "",
+            "overview": "",
+        }))
+        .unwrap();
+        assert_eq!(missing.air_date, None);
+        assert!(serde_json::from_value::<EpisodeDetails>(json!({
+            "episode_number": 1,
+            "season_number": 1,
+            "name": "",
+            "air_date": 2024,
+            "overview": "",
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn next_season_starts_only_when_published() {
+        let seasonal = |season_number, episode_number| {
+            Episode::Seasonal(SeasonEpisode {
+                episode_number,
+                season_number,
+            })
+        };
+        let start = |season_number| {
+            Some(SeasonEpisode {
+                episode_number: 1,
+                season_number,
+            })
+        };
+        let details = test_details(1, &[10, 8, 6], (3, 2), None);
+        assert_eq!(details.next_season_start(&seasonal(1, 4)), start(2));
+        assert_eq!(details.next_season_start(&seasonal(2, 8)), start(3));
+        assert_eq!(
+            details.next_season_start(&Episode::Total(TotalEpisode { episode: 4 })),
+            start(2)
+        );
+        // There is no fourth season
+        assert_eq!(details.next_season_start(&seasonal(3, 1)), None);
+        // The third season is announced but did not start yet
+        let details = test_details(1, &[10, 8, 6], (2, 8), None);
+        assert_eq!(details.next_season_start(&seasonal(2, 3)), None);
+        assert_eq!(details.next_season_start(&seasonal(1, 10)), start(2));
+    }
+}
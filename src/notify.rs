@@ -0,0 +1,308 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Local, Timelike, Utc};
+use iced::Command;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::bookmark::Bookmark;
+use crate::config::QuietHours;
+use crate::message::Message;
+use crate::movie_details::SeasonEpisode;
+use crate::state::State;
+
+/// Maximum number of notifications kept in the history
+const HISTORY_SIZE: usize = 100;
+
+/// Delivers notifications to the user
+pub trait Notifier: Debug + Send + Sync {
+    fn notify(&self, summary: &str, body: &str) -> Result<()>;
+}
+/// Notifier which uses the freedesktop notification specification over D-Bus
+#[derive(Debug, Default)]
+pub struct DesktopNotifier;
+
+#[cfg(target_os = "linux")]
+impl Notifier for DesktopNotifier {
+    fn notify(&self, summary: &str, body: &str) -> Result<()> {
+        use std::collections::HashMap;
+        use zbus::zvariant::Value;
+
+        let connection = zbus::blocking::Connection::session()?;
+        connection.call_method(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            Some("org.freedesktop.Notifications"),
+            "Notify",
+            &(
+                "Webworm",
+                0u32,
+                "",
+                summary,
+                body,
+                Vec::<&str>::new(),
+                HashMap::<&str, Value>::new(),
+                -1i32,
+            ),
+        )?;
+        Ok(())
+    }
+}
+#[cfg(not(target_os = "linux"))]
+impl Notifier for DesktopNotifier {
+    fn notify(&self, summary: &str, _body: &str) -> Result<()> {
+        info!("desktop notifications are not supported on this platform: {summary}");
+        Ok(())
+    }
+}
+/// Shared handle to the notifier used by the app
+#[derive(Debug, Clone)]
+pub struct NotifierHandle(pub Arc<dyn Notifier>);
+impl Default for NotifierHandle {
+    fn default() -> Self {
+        NotifierHandle(Arc::new(DesktopNotifier))
+    }
+}
+/// Entry of the notification history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub time: DateTime<Utc>,
+    pub summary: String,
+    pub body: String,
+    /// False while the notification is held back during quiet hours
+    pub delivered: bool,
+}
+impl QuietHours {
+    /// Whether the hour of `time` lies in the quiet hours.
+    /// The quiet hours may wrap around midnight
+    pub fn contains(&self, time: DateTime<Local>) -> bool {
+        let hour = time.hour();
+        if self.start <= self.end {
+            self.start <= hour && hour < self.end
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+impl State {
+    /// Notifies about a newly aired episode of the bookmark.
+    /// Muted bookmarks are ignored and notifications in the quiet hours only end up in the history
+    pub fn notify_new_episode(
+        &mut self,
+        bookmark: &Bookmark,
+        episode: &SeasonEpisode,
+        now: DateTime<Local>,
+    ) -> Command<Message> {
+        if bookmark.muted {
+            debug!("bookmark {} is muted", bookmark.movie.id);
+            return Command::none();
+        }
+        let delivered = !self.is_quiet(now);
+        let notification = Notification {
+            time: now.with_timezone(&Utc),
+            summary: format!("New episode of {}", bookmark.movie.name),
+            body: format!("Episode {} aired", episode.as_info_str()),
+            delivered,
+        };
+        self.notifications.insert(0, notification.clone());
+        self.notifications.truncate(HISTORY_SIZE);
+        if !delivered {
+            info!("hold back notification during quiet hours");
+            return Command::none();
+        }
+        self.send(notification)
+    }
+    /// Sends the notifications held back during the quiet hours once they are over
+    pub fn deliver_held_back(&mut self, now: DateTime<Local>) -> Command<Message> {
+        if self.is_quiet(now) {
+            return Command::none();
+        }
+        let mut held_back = Vec::new();
+        // The history starts with the newest notification
+        for notification in self.notifications.iter_mut().rev() {
+            if !notification.delivered {
+                notification.delivered = true;
+                held_back.push(notification.clone());
+            }
+        }
+        if !held_back.is_empty() {
+            info!("deliver {} held back notifications", held_back.len());
+        }
+        Command::batch(
+            held_back
+                .into_iter()
+                .map(|notification| self.send(notification)),
+        )
+    }
+    pub fn has_held_back_notifications(&self) -> bool {
+        self.notifications
+            .iter()
+            .any(|notification| !notification.delivered)
+    }
+    pub(crate) fn is_quiet(&self, now: DateTime<Local>) -> bool {
+        self.config
+            .quiet_hours
+            .is_some_and(|quiet_hours| quiet_hours.contains(now))
+    }
+    fn send(&self, notification: Notification) -> Command<Message> {
+        let notifier = self.notifier.0.clone();
+        Command::perform(
+            async move {
+                notifier
+                    .notify(&notification.summary, &notification.body)
+                    .map_err(|e| e.to_string())
+            },
+            Message::Notified,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use chrono::TimeZone;
+    use iced_runtime::command::Action;
+    use serde_json::json;
+
+    use super::*;
+    use crate::movie::test_movie;
+
+    /// Remembers the notifications instead of showing them
+    #[derive(Debug, Default)]
+    struct RecordingNotifier {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+    impl Notifier for RecordingNotifier {
+        fn notify(&self, summary: &str, body: &str) -> Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((summary.to_owned(), body.to_owned()));
+            Ok(())
+        }
+    }
+
+    fn state_with_show(muted: bool) -> (State, Arc<RecordingNotifier>) {
+        let notifier = Arc::new(RecordingNotifier::default());
+        let movie = test_movie(1, "Show");
+        let mut bookmark = Bookmark::from(&movie);
+        bookmark.muted = muted;
+        let state = State {
+            bookmarks: vec![bookmark],
+            notifier: NotifierHandle(notifier.clone()),
+            ..State::default()
+        };
+        (state, notifier)
+    }
+    /// Details of a show with one season of which `latest` episodes are published
+    fn details(latest: usize) -> String {
+        json!({
+            "id": 1,
+            "seasons": [{
+                "id": 10,
+                "name": "Season 1",
+                "episode_count": 10,
+                "season_number": 1,
+                "overview": "",
+                "poster_path": null,
+            }],
+            "in_production": true,
+            "last_air_date": "2024-01-01",
+            "number_of_seasons": 1,
+            "number_of_episodes": 10,
+            "last_episode_to_air": {
+                "episode_number": latest,
+                "season_number": 1,
+                "name": "",
+                "air_date": "2024-01-01",
+                "overview": "",
+            },
+            "next_episode_to_air": null,
+        })
+        .to_string()
+    }
+    /// Runs the futures of the command like the runtime would
+    fn run(command: Command<Message>) {
+        for action in command.actions() {
+            if let Action::Future(future) = action {
+                async_std::task::block_on(future);
+            }
+        }
+    }
+    fn at(hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 1, 1, hour, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn notifies_about_new_episodes() {
+        let (mut state, notifier) = state_with_show(false);
+        // The first response only tells which episodes are known
+        run(state.response_tv_details(details(3)));
+        assert!(notifier.sent.lock().unwrap().is_empty());
+        run(state.response_tv_details(details(3)));
+        assert!(notifier.sent.lock().unwrap().is_empty());
+
+        run(state.response_tv_details(details(5)));
+        assert_eq!(
+            *notifier.sent.lock().unwrap(),
+            [(
+                "New episode of Show".to_owned(),
+                "Episode 5E · 1S aired".to_owned()
+            )]
+        );
+        assert_eq!(state.new_since_launch, [(state.bookmarks[0].movie.id, 2)]);
+        assert_eq!(state.notifications.len(), 1);
+        assert!(state.notifications[0].delivered);
+    }
+    #[test]
+    fn ignores_muted_bookmarks() {
+        let (mut state, notifier) = state_with_show(true);
+        run(state.response_tv_details(details(3)));
+        run(state.response_tv_details(details(4)));
+        assert!(notifier.sent.lock().unwrap().is_empty());
+        assert!(state.notifications.is_empty());
+    }
+    #[test]
+    fn holds_back_notifications_during_quiet_hours() {
+        let (mut state, notifier) = state_with_show(false);
+        state.config.quiet_hours = Some(QuietHours { start: 22, end: 8 });
+        let bookmark = state.bookmarks[0].clone();
+        let episode = SeasonEpisode {
+            episode_number: 4,
+            season_number: 1,
+        };
+        run(state.notify_new_episode(&bookmark, &episode, at(23)));
+        assert!(notifier.sent.lock().unwrap().is_empty());
+        assert!(state.has_held_back_notifications());
+        assert_eq!(state.notifications[0].time, at(23));
+
+        run(state.deliver_held_back(at(7)));
+        assert!(notifier.sent.lock().unwrap().is_empty());
+
+        run(state.deliver_held_back(at(8)));
+        assert_eq!(notifier.sent.lock().unwrap().len(), 1);
+        assert!(!state.has_held_back_notifications());
+        // Delivered notifications are not sent twice
+        run(state.deliver_held_back(at(9)));
+        assert_eq!(notifier.sent.lock().unwrap().len(), 1);
+        // Outside the quiet hours notifications are sent right away
+        run(state.notify_new_episode(&bookmark, &episode, at(12)));
+        assert_eq!(notifier.sent.lock().unwrap().len(), 2);
+    }
+    #[test]
+    fn quiet_hours_wrap_around_midnight() {
+        let night = QuietHours { start: 22, end: 8 };
+        assert!(night.contains(at(23)));
+        assert!(night.contains(at(0)));
+        assert!(night.contains(at(7)));
+        assert!(!night.contains(at(8)));
+        assert!(!night.contains(at(21)));
+        let afternoon = QuietHours { start: 12, end: 14 };
+        assert!(afternoon.contains(at(13)));
+        assert!(!afternoon.contains(at(14)));
+        assert!(!afternoon.contains(at(11)));
+    }
+}
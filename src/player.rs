@@ -0,0 +1,268 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use iced::Command;
+use serde_json::{json, Value};
+use tracing::{debug, error, info, warn};
+
+use crate::bookmark::Bookmark;
+use crate::config::{AdvanceMode, Config};
+use crate::launch;
+use crate::message::{BookmarkMessage, Message};
+use crate::movie_details::MovieDetails;
+use crate::save;
+
+/// How often connecting to the socket is tried while mpv starts up
+const CONNECT_ATTEMPTS: usize = 50;
+const CONNECT_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Starts the player for the active episode of the bookmark.
+/// If the player is mpv, the bookmark is advanced once the playback passed the configured threshold.
+/// Other players can't report their progress, so the bookmark is advanced according to `mode` right away
+pub fn play(
+    argv: &[String],
+    bookmark: &mut Bookmark,
+    details: Option<MovieDetails>,
+    mode: AdvanceMode,
+    config: &Config,
+) -> Command<Message> {
+    let track = cfg!(unix) && config.track_player_progress && mode != AdvanceMode::Never;
+    if !track || !is_mpv(argv) {
+        if let Err(e) = launch::spawn(argv) {
+            error!("failed to run {argv:?}: {e}");
+            return Command::none();
+        }
+        return advance(bookmark, details, mode);
+    }
+    let socket = socket_path(bookmark);
+    if let Err(e) = launch::spawn(&with_ipc_server(argv, &socket)) {
+        error!("failed to run {argv:?}: {e}");
+        return Command::none();
+    }
+    let id = bookmark.movie.id;
+    let episode = bookmark.active_episode().clone();
+    Command::perform(watch_progress(socket), move |progress| {
+        Message::PlaybackEnded(id, episode, progress)
+    })
+}
+/// Advances the bookmark after an episode was started, or asks whether it was watched
+pub fn advance(
+    bookmark: &mut Bookmark,
+    details: Option<MovieDetails>,
+    mode: AdvanceMode,
+) -> Command<Message> {
+    match mode {
+        AdvanceMode::Always => bookmark.apply(BookmarkMessage::IncrE(details)),
+        AdvanceMode::Never => {
+            debug!("the bookmark is not advanced");
+            Command::none()
+        }
+        AdvanceMode::Ask => {
+            let id = bookmark.movie.id;
+            let episode = bookmark.active_episode().clone();
+            Command::perform(async {}, move |()| Message::AskAdvance(id, episode))
+        }
+    }
+}
+/// True if the program of the command is mpv
+fn is_mpv(argv: &[String]) -> bool {
+    argv.first()
+        .and_then(|program| Path::new(program).file_stem())
+        .is_some_and(|name| name == "mpv")
+}
+/// Inserts the option which lets mpv listen for JSON IPC commands on `socket`
+fn with_ipc_server(argv: &[String], socket: &Path) -> Vec<String> {
+    let mut argv = argv.to_vec();
+    argv.insert(1, format!("--input-ipc-server={}", socket.display()));
+    argv
+}
+/// Socket in a directory only the user can write to, the runtime directory if there is one.
+/// The random part keeps other processes from guessing the name
+fn socket_path(bookmark: &Bookmark) -> PathBuf {
+    let mut path = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(save::data_path);
+    if let Err(e) = std::fs::create_dir_all(&path) {
+        warn!("failed to create the socket directory {path:?}: {e}");
+    }
+    let random = RandomState::new().build_hasher().finish();
+    path.push(format!(
+        "webworm-mpv-{}-{random:016x}.sock",
+        bookmark.movie.id.id()
+    ));
+    path
+}
+/// Follows the playback through the IPC socket of mpv until the player is closed.
+/// Returns the furthest position reached in percent of the file
+#[cfg(unix)]
+pub async fn watch_progress(socket: PathBuf) -> Result<f64, String> {
+    use async_std::io::{prelude::BufReadExt, BufReader, WriteExt};
+    use async_std::os::unix::net::UnixStream;
+
+    let mut stream = None;
+    for _ in 0..CONNECT_ATTEMPTS {
+        if let Ok(connected) = UnixStream::connect(&socket).await {
+            stream = Some(connected);
+            break;
+        }
+        async_std::task::sleep(CONNECT_INTERVAL).await;
+    }
+    let Some(mut stream) = stream else {
+        warn!("couldn't connect to the mpv socket {socket:?}");
+        return Err(format!("Can't connect to mpv at {}", socket.display()));
+    };
+    let observe = json!({ "command": ["observe_property", 1, "percent-pos"] });
+    stream
+        .write_all(format!("{observe}\n").as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut lines = BufReader::new(stream).lines();
+    let mut progress: f64 = 0.;
+    // The connection is closed when mpv exits
+    while let Some(Ok(line)) = async_std::stream::StreamExt::next(&mut lines).await {
+        let Ok(event) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        match event["event"].as_str() {
+            Some("property-change") if event["name"] == "percent-pos" => {
+                if let Some(position) = event["data"].as_f64() {
+                    progress = progress.max(position);
+                }
+            }
+            Some("end-file") if event["reason"] == "eof" => progress = 100.,
+            _ => {}
+        }
+    }
+    let _ = std::fs::remove_file(&socket);
+    info!("playback ended at {progress:.0}%");
+    Ok(progress)
+}
+#[cfg(not(unix))]
+pub async fn watch_progress(_socket: PathBuf) -> Result<f64, String> {
+    Err("Following the playback is only supported on unix".into())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixListener;
+
+    use iced_runtime::command::Action;
+
+    use super::*;
+    use crate::bookmark::SyncMode;
+    use crate::id::MovieId;
+    use crate::movie::test_movie;
+    use crate::state::State;
+
+    /// Stands in for mpv: answers the observe command with the given events, then quits
+    fn fake_mpv(name: &str, events: &'static [&'static str]) -> PathBuf {
+        let mut socket = std::env::temp_dir();
+        socket.push(format!("webworm-test-{name}-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut command = String::new();
+            BufReader::new(&stream).read_line(&mut command).unwrap();
+            assert!(command.contains("observe_property"));
+            for event in events {
+                writeln!(stream, "{event}").unwrap();
+            }
+        });
+        socket
+    }
+    fn bookmark(id: usize) -> Bookmark {
+        let movie = test_movie(id, "Show");
+        let mut bookmark = Bookmark::from(&movie);
+        bookmark.sync_mode = SyncMode::NoSync;
+        bookmark
+    }
+    /// Asks about the active episode of the bookmark like playing it in ask mode does
+    fn ask(state: &mut State, index: usize) {
+        let command = advance(&mut state.bookmarks[index], None, AdvanceMode::Ask);
+        for action in command.actions() {
+            if let Action::Future(future) = action {
+                let message = async_std::task::block_on(future);
+                assert!(matches!(message, Message::AskAdvance(..)));
+                let _ = state.update_state(message);
+            }
+        }
+    }
+
+    #[test]
+    fn reports_the_furthest_position() {
+        let socket = fake_mpv(
+            "position",
+            &[
+                r#"{"event":"property-change","id":1,"name":"percent-pos","data":42.5}"#,
+                r#"{"event":"property-change","id":1,"name":"percent-pos","data":87.0}"#,
+                "not json",
+                r#"{"event":"property-change","id":1,"name":"percent-pos","data":12.0}"#,
+                r#"{"event":"end-file","reason":"quit"}"#,
+            ],
+        );
+        let progress = async_std::task::block_on(watch_progress(socket.clone()));
+        assert_eq!(progress, Ok(87.));
+        assert!(!socket.exists());
+    }
+    #[test]
+    fn counts_the_end_of_the_file_as_watched() {
+        let socket = fake_mpv(
+            "eof",
+            &[
+                r#"{"event":"property-change","id":1,"name":"percent-pos","data":60.0}"#,
+                r#"{"event":"end-file","reason":"eof"}"#,
+            ],
+        );
+        let progress = async_std::task::block_on(watch_progress(socket));
+        assert_eq!(progress, Ok(100.));
+    }
+    #[test]
+    fn inserts_the_ipc_server_after_the_program() {
+        let argv = vec!["/usr/bin/mpv".to_string(), "episode.mkv".to_string()];
+        assert!(is_mpv(&argv));
+        assert_eq!(
+            with_ipc_server(&argv, Path::new("/run/user/1000/mpv.sock")),
+            [
+                "/usr/bin/mpv",
+                "--input-ipc-server=/run/user/1000/mpv.sock",
+                "episode.mkv"
+            ]
+        );
+        assert!(!is_mpv(&["vlc".to_string()]));
+    }
+    #[test]
+    fn keeps_a_question_per_bookmark() {
+        let mut state = State {
+            bookmarks: vec![bookmark(1), bookmark(2)],
+            ..State::default()
+        };
+        let first = state.bookmarks[0].active_episode().clone();
+        ask(&mut state, 0);
+        ask(&mut state, 1);
+        ask(&mut state, 0);
+        let asked: Vec<MovieId> = state
+            .gui
+            .advance_prompts
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(
+            asked,
+            [state.bookmarks[0].movie.id, state.bookmarks[1].movie.id]
+        );
+        // Answering one question keeps the other
+        let second = state.bookmarks[1].movie.id;
+        let _ = state.update_state(Message::AnswerAdvance(second, true));
+        assert_eq!(state.gui.advance_prompts.len(), 1);
+        assert_ne!(state.bookmarks[1].active_episode(), &first);
+        let id = state.bookmarks[0].movie.id;
+        let _ = state.update_state(Message::AnswerAdvance(id, false));
+        assert!(state.gui.advance_prompts.is_empty());
+        assert_eq!(state.bookmarks[0].active_episode(), &first);
+    }
+}
@@ -1,10 +1,13 @@
+use std::time::SystemTime;
+
+use chrono::Local;
 use iced::Command;
 use tracing::{error, info};
 
 use crate::{
-    id::EpisodeId,
+    id::{EpisodeId, MovieId},
     message::Message,
-    movie_details::{Episode, EpisodeDetails, MovieDetails},
+    movie_details::{Episode, EpisodeDetails, MovieDetails, SeasonDetails},
     state::State,
     tmdb::{RequestType, TmdbResponse},
 };
@@ -38,6 +41,7 @@ impl State {
             return Command::none();
         };
         response.fix_episode_formats();
+        let mut aired = None;
         if let Some(bookmark) = self
             .bookmarks
             .iter_mut()
@@ -46,17 +50,72 @@ impl State {
             if let Episode::Total(e) = &bookmark.current_episode {
                 bookmark.current_episode = response.as_seasonal_episode(e).into();
             }
+            if let Some(Episode::Total(e)) = &bookmark.rewatch {
+                bookmark.rewatch = Some(response.as_seasonal_episode(e).into());
+            }
+            if let Some(last_published) = response.last_published() {
+                let latest = last_published.episode;
+                if let Some(last_known) = &bookmark.last_known_episode {
+                    let new_episodes = response
+                        .as_total_episodes(&latest)
+                        .episode
+                        .saturating_sub(response.as_total_episodes(last_known).episode);
+                    if new_episodes > 0 {
+                        info!(
+                            "{} new episodes published for {}",
+                            new_episodes, bookmark.movie.name
+                        );
+                        match self
+                            .new_since_launch
+                            .iter_mut()
+                            .find(|(id, _)| *id == response.id)
+                        {
+                            Some((_, count)) => *count += new_episodes,
+                            None => self.new_since_launch.push((response.id, new_episodes)),
+                        }
+                        aired = Some((bookmark.clone(), latest.clone()));
+                    }
+                }
+                bookmark.last_known_episode = Some(latest);
+            }
             if bookmark.finished {
                 let next = response.next_episode(bookmark.current_episode.clone());
                 if next != bookmark.current_episode {
                     info!("Found new episode for {:?}. Reset finished state", bookmark);
-                    bookmark.finished = false;
+                    bookmark.set_finished(false);
                     bookmark.current_episode = next;
                 }
             }
         }
+        // Request the season of the next episode to know about further scheduled episodes
+        let cmd = match response.next_episode_to_air() {
+            Some(next) if self.get_bookmark(response.id).is_some() => {
+                let request = RequestType::SeasonDetails {
+                    id: response.id,
+                    season: next.episode.season_number,
+                };
+                self.update_state(Message::ExecuteRequest(request))
+                    .command()
+            }
+            _ => Command::none(),
+        };
+        let notify = match aired {
+            Some((bookmark, episode)) => self.notify_new_episode(&bookmark, &episode, Local::now()),
+            None => Command::none(),
+        };
+        self.details_refreshed
+            .insert(response.id, SystemTime::now());
         self.movie_details.insert(response.id, response);
-        Command::none()
+        Command::batch([cmd, notify, self.write_calendar_subscription()])
+    }
+    pub fn response_season_details(&mut self, text: String, id: MovieId) -> Command<Message> {
+        let response: serde_json::Result<SeasonDetails> = serde_json::from_str(&text);
+        let Ok(response) = response else {
+            error!("Failed to parse season details with: {response:?}");
+            return Command::none();
+        };
+        self.season_details.insert(id, response);
+        self.write_calendar_subscription()
     }
     pub fn response_episode_details(&mut self, text: String, id: EpisodeId) -> Command<Message> {
         let response: serde_json::Result<EpisodeDetails> = serde_json::from_str(&text);
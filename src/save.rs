@@ -1,4 +1,10 @@
-use std::{collections::HashMap, fs::File, io::Write};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::Write,
+    path::PathBuf,
+    sync::atomic::{self, AtomicUsize},
+};
 
 use async_std::{fs::create_dir_all, io::ReadExt};
 use iced::{
@@ -6,22 +12,43 @@ use iced::{
     Command,
 };
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, warn};
+use tracing::{debug, error, info, warn};
 
 use crate::{
+    archive::ArchivedBookmark,
     bookmark::Bookmark,
+    collection::Collection,
+    config::Config,
     gui::App,
-    id::MovieId,
-    link::Link,
+    id::{EpisodeId, MovieId},
+    keymap,
+    link::BookmarkLinks,
     message::Message,
+    notify::Notification,
     state::State,
+    stats::WatchEvent,
+    theme,
     tmdb::{self, RequestType},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SavedState {
     pub bookmarks: Vec<Bookmark>,
-    pub links: HashMap<MovieId, Link>,
+    pub links: HashMap<MovieId, BookmarkLinks>,
+    /// Older states contain the config, which is now saved in its own file
+    #[serde(default, skip_serializing)]
+    pub config: Config,
+    #[serde(default)]
+    pub notifications: Vec<Notification>,
+    #[serde(default)]
+    pub collections: Vec<Collection>,
+    /// Map entries, because JSON only supports strings as keys
+    #[serde(default)]
+    pub episode_notes: Vec<(EpisodeId, String)>,
+    #[serde(default)]
+    pub watch_log: Vec<WatchEvent>,
+    #[serde(default)]
+    pub archive: Vec<ArchivedBookmark>,
 }
 
 #[derive(Debug, Clone)]
@@ -43,7 +70,8 @@ pub enum SaveError {
     Format,
 }
 
-fn path() -> std::path::PathBuf {
+/// Directory containing the state and all other files of the app
+pub fn data_path() -> std::path::PathBuf {
     if let Some(project_dirs) = directories_next::ProjectDirs::from("", "", "Webworm") {
         project_dirs.data_dir().into()
     } else {
@@ -52,14 +80,71 @@ fn path() -> std::path::PathBuf {
     }
 }
 fn poster_path() -> std::path::PathBuf {
-    let mut path = path();
+    let mut path = data_path();
     path.push("posters");
     path
 }
+/// Location of the config file next to the state file
+pub fn config_path() -> PathBuf {
+    let mut path = data_path();
+    path.push("config.toml");
+    path
+}
+/// Default location of the exported iCalendar file
+pub fn calendar_path() -> PathBuf {
+    let mut path = data_path();
+    path.push("calendar.ics");
+    path
+}
+/// Default location of the exported statistics
+pub fn stats_path() -> PathBuf {
+    let mut path = data_path();
+    path.push("stats.json");
+    path
+}
+/// Default location of exported bookmarks
+pub fn bookmarks_export_path() -> PathBuf {
+    let mut path = data_path();
+    path.push("bookmarks-export.json");
+    path
+}
+/// Writes an exported file to `path`, creating missing directories
+pub async fn write_export(path: PathBuf, contents: String) -> Result<PathBuf, SaveError> {
+    use async_std::prelude::*;
+
+    if let Some(dir) = path.parent() {
+        async_std::fs::create_dir_all(dir)
+            .await
+            .map_err(trace_io_error)
+            .map_err(|_| SaveError::File)?;
+    }
+    // Readers of the file never see it half written, the complete file is moved into place.
+    // Every write has its own temporary file, so concurrent writes of the same path can't mix
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let write = WRITES.fetch_add(1, atomic::Ordering::Relaxed);
+    let mut temp_path = path.clone().into_os_string();
+    temp_path.push(format!(".{}-{write}.tmp", std::process::id()));
+    let temp_path = PathBuf::from(temp_path);
+    {
+        let mut file = async_std::fs::File::create(&temp_path)
+            .await
+            .map_err(trace_io_error)
+            .map_err(|_| SaveError::File)?;
+        file.write_all(contents.as_bytes())
+            .await
+            .map_err(trace_io_error)
+            .map_err(|_| SaveError::Write)?;
+    }
+    async_std::fs::rename(&temp_path, &path)
+        .await
+        .map_err(trace_io_error)
+        .map_err(|_| SaveError::Write)?;
+    Ok(path)
+}
 impl SavedState {
     pub async fn load() -> Result<SavedState, LoadError> {
         let mut contents = String::new();
-        let path = path();
+        let path = data_path();
         let mut state_path = path.clone();
         state_path.push("state.json");
         let mut state_file = if state_path.is_file() {
@@ -85,16 +170,27 @@ impl SavedState {
                 LoadError::ReadFile
             })?;
 
-        serde_json::from_str::<SavedState>(&contents)
-            .map_err(|e| LoadError::DeserializationError(e.to_string()))
+        let mut state = serde_json::from_str::<SavedState>(&contents)
+            .map_err(|e| LoadError::DeserializationError(e.to_string()))?;
+        // A broken config file must not prevent the bookmarks from loading
+        match Config::load() {
+            Ok(Some(config)) => state.config = config,
+            Ok(None) => info!("no config file, using the config of the state"),
+            Err(e) => error!("failed to load the config, using the config of the state: {e}"),
+        }
+        Ok(state)
     }
 
-    pub async fn save(self) -> Result<(), SaveError> {
+    /// Writes the state. The config file is only written if `write_config` is set
+    pub async fn save(self, write_config: bool) -> Result<(), SaveError> {
         use async_std::prelude::*;
 
         let json = serde_json::to_string_pretty(&self).map_err(|_| SaveError::Format)?;
+        let toml = toml::to_string_pretty(&self.config)
+            .map_err(trace_io_error)
+            .map_err(|_| SaveError::Format)?;
 
-        let mut path = path();
+        let mut path = data_path();
         path.push("state.json");
 
         if let Some(dir) = path.parent() {
@@ -117,8 +213,16 @@ impl SavedState {
                 .map_err(trace_io_error)?;
         }
 
+        if write_config {
+            debug!("writing the config file");
+            async_std::fs::write(config_path(), toml)
+                .await
+                .map_err(trace_io_error)
+                .map_err(|_| SaveError::Write)?;
+        }
+
         // This is a simple way to save at most once every couple seconds
-        async_std::task::sleep(std::time::Duration::from_secs(5)).await;
+        async_std::task::sleep(self.config.save_delay()).await;
 
         Ok(())
     }
@@ -127,13 +231,17 @@ fn trace_io_error<T: std::fmt::Debug>(t: T) -> T {
     error!("Saving/Loading failed with {t:?}");
     t
 }
+/// Name of the cached poster file of the movie
+fn poster_file_name(id: MovieId) -> String {
+    format!("{}.png", id)
+}
 pub async fn load_poster(id: MovieId, url: String) -> anyhow::Result<Handle> {
     let mut path = poster_path();
     if !path.exists() {
         let _ = create_dir_all(&path).await;
         warn!("created poster folder");
     }
-    path.push(format!("{}.png", id));
+    path.push(poster_file_name(id));
     if path.exists() {
         let bytes = async_std::fs::read(path).await?;
         let handle = image::Handle::from_memory(bytes);
@@ -146,14 +254,42 @@ pub async fn load_poster(id: MovieId, url: String) -> anyhow::Result<Handle> {
         Ok(handle)
     }
 }
+/// Deletes the cached posters of the movies. Returns how many were deleted
+pub async fn remove_posters(ids: HashSet<MovieId>) -> usize {
+    ids.into_iter()
+        .map(|id| {
+            let mut path = poster_path();
+            path.push(poster_file_name(id));
+            path
+        })
+        .filter(|path| path.is_file())
+        .filter(|path| match std::fs::remove_file(path) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("failed to remove poster {path:?}: {e}");
+                false
+            }
+        })
+        .count()
+}
 impl App {
     pub fn as_loaded(&mut self, state: SavedState) -> Command<Message> {
         // set self to be loaded
-        *self = App::Loaded(State {
+        let mut loaded = State {
             bookmarks: state.bookmarks.clone(),
             links: state.links,
+            config: state.config.clone(),
+            notifications: state.notifications,
+            collections: state.collections,
+            episode_notes: state.episode_notes.into_iter().collect(),
+            watch_log: state.watch_log,
+            archive: state.archive,
+            saved_config: config_path().is_file().then(|| state.config.clone()),
             ..State::default()
-        });
+        };
+        loaded.apply_theme();
+        loaded.gui.window_width = loaded.config.window_width;
+        *self = App::Loaded(loaded);
         // load new data for the bookmarks
         let iter_load_details = state
             .bookmarks
@@ -184,7 +320,47 @@ impl App {
                     Message::ExecuteRequest(req)
                 })
             });
+        let iter_scan_libraries = state
+            .bookmarks
+            .iter()
+            .filter(|bookmark| bookmark.local_dir.is_some())
+            .map(|bookmark| Message::ScanLibrary(bookmark.movie.id))
+            .map(|msg| Command::perform(async { Ok(()) }, |_: Result<(), ()>| msg));
         debug!("Finished loading the app state. Loading details and images next");
-        Command::batch(iter_load_details.chain(iter_load_posters))
+        let load_themes = [
+            Command::perform(theme::load_custom_themes(), Message::ThemesLoaded),
+            Command::perform(theme::detect_system_dark(), Message::SystemThemeDetected),
+            Command::perform(keymap::load_keymap(), Message::KeymapLoaded),
+        ];
+        Command::batch(
+            iter_load_details
+                .chain(iter_load_posters)
+                .chain(iter_scan_libraries)
+                .chain(load_themes),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_exports_never_mix() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("webworm-export-{}", std::process::id()));
+        path.push("calendar.ics");
+        let contents: Vec<String> = (0..8).map(|i| i.to_string().repeat(100_000)).collect();
+        let writes: Vec<_> = contents
+            .iter()
+            .map(|contents| async_std::task::spawn(write_export(path.clone(), contents.clone())))
+            .collect();
+        let results: Vec<_> = writes.into_iter().map(async_std::task::block_on).collect();
+        assert!(results.iter().all(Result::is_ok));
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains(&written));
+        let dir = path.parent().unwrap();
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
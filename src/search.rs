@@ -0,0 +1,458 @@
+use std::cmp::{Ordering, Reverse};
+
+use chrono::{Datelike, Days, NaiveDate};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::bookmark::{Bookmark, WatchStatus};
+use crate::calendar;
+use crate::collection::parse_tag;
+use crate::movie_details::MovieDetails;
+use crate::state::{InputKind, State};
+
+/// Text reduced to lowercase characters without diacritics,
+/// remembering which character of the original text each character came from
+struct Folded {
+    chars: Vec<char>,
+    origins: Vec<usize>,
+}
+impl Folded {
+    fn new(text: &str) -> Self {
+        let mut chars = Vec::new();
+        let mut origins = Vec::new();
+        for (index, c) in text.chars().enumerate() {
+            for folded in c
+                .nfd()
+                .filter(|c| !is_combining_mark(*c))
+                .flat_map(char::to_lowercase)
+            {
+                chars.push(folded);
+                origins.push(index);
+            }
+        }
+        Self { chars, origins }
+    }
+    fn is_word_start(&self, index: usize) -> bool {
+        index == 0 || !self.chars[index - 1].is_alphanumeric()
+    }
+}
+/// Lowercases the text and removes diacritics, so `Pokémon` becomes `pokemon`
+pub fn fold(text: &str) -> String {
+    Folded::new(text).chars.into_iter().collect()
+}
+/// Where and how well a term matched a text
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FuzzyMatch {
+    score: i32,
+    /// Indices of the matched characters in the original text
+    positions: Vec<usize>,
+}
+/// Matches the characters of the term in order, preferring a contiguous match.
+/// Returns `None` if the text doesn't contain all characters of the term in order
+fn fuzzy_match(term: &[char], text: &Folded) -> Option<FuzzyMatch> {
+    if term.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    let contiguous = text
+        .chars
+        .windows(term.len())
+        .enumerate()
+        .filter(|(_, window)| *window == term)
+        .map(|(start, _)| start)
+        .max_by_key(|start| (text.is_word_start(*start), Reverse(*start)));
+    if let Some(start) = contiguous {
+        let mut score = 100 + 10 * term.len() as i32;
+        if text.is_word_start(start) {
+            score += 20;
+        }
+        score -= start.min(20) as i32;
+        let positions = (start..start + term.len())
+            .map(|index| text.origins[index])
+            .collect();
+        return Some(FuzzyMatch { score, positions });
+    }
+    let mut score = 0;
+    let mut positions = Vec::with_capacity(term.len());
+    let mut previous: Option<usize> = None;
+    let mut index = 0;
+    for c in term {
+        let found = (index..text.chars.len()).find(|i| text.chars[*i] == *c)?;
+        score += 10;
+        match previous {
+            Some(previous) if previous + 1 == found => score += 5,
+            Some(previous) => score -= (found - previous - 1).min(5) as i32,
+            None => score -= found.min(10) as i32,
+        }
+        if text.is_word_start(found) {
+            score += 8;
+        }
+        positions.push(text.origins[found]);
+        previous = Some(found);
+        index = found + 1;
+    }
+    Some(FuzzyMatch { score, positions })
+}
+/// Condition of a `field:value` term
+#[derive(Debug, Clone, PartialEq)]
+enum FieldQuery {
+    /// The bookmark has a tag containing the text
+    Tag(String),
+    Status(WatchStatus),
+    /// Compares the number of episodes behind, e.g. `behind:>3`
+    Behind(Ordering, bool, usize),
+    /// The next episode airs within the dates
+    Airs(NaiveDate, NaiveDate),
+}
+impl FieldQuery {
+    fn parse(field: &str, value: &str, today: NaiveDate) -> Option<Self> {
+        match field {
+            "tag" => parse_tag(value).map(|tag| FieldQuery::Tag(fold(&tag))),
+            "status" => {
+                let value: String = fold(value)
+                    .chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .collect();
+                if value.is_empty() {
+                    return None;
+                }
+                WatchStatus::ALL
+                    .into_iter()
+                    .find(|status| {
+                        let name: String = fold(&status.to_string())
+                            .chars()
+                            .filter(|c| c.is_alphanumeric())
+                            .collect();
+                        name.starts_with(&value)
+                    })
+                    .map(FieldQuery::Status)
+            }
+            "behind" => {
+                let (ordering, or_equal, number) = if let Some(number) = value.strip_prefix(">=") {
+                    (Ordering::Greater, true, number)
+                } else if let Some(number) = value.strip_prefix("<=") {
+                    (Ordering::Less, true, number)
+                } else if let Some(number) = value.strip_prefix('>') {
+                    (Ordering::Greater, false, number)
+                } else if let Some(number) = value.strip_prefix('<') {
+                    (Ordering::Less, false, number)
+                } else {
+                    (Ordering::Equal, true, value.trim_start_matches('='))
+                };
+                let number = number.parse().ok()?;
+                Some(FieldQuery::Behind(ordering, or_equal, number))
+            }
+            "airs" => {
+                let (start, end) = match fold(value).as_str() {
+                    "today" => (today, today),
+                    "tomorrow" => (today + Days::new(1), today + Days::new(1)),
+                    "thisweek" => {
+                        let week = today.week(chrono::Weekday::Mon);
+                        (week.first_day(), week.last_day())
+                    }
+                    "thismonth" => {
+                        let start = today.with_day(1)?;
+                        let end = start.checked_add_months(chrono::Months::new(1))? - Days::new(1);
+                        (start, end)
+                    }
+                    _ => return None,
+                };
+                Some(FieldQuery::Airs(start, end))
+            }
+            _ => None,
+        }
+    }
+    fn matches(&self, bookmark: &Bookmark, details: Option<&MovieDetails>) -> bool {
+        match self {
+            FieldQuery::Tag(tag) => bookmark.tags.iter().any(|t| fold(t).contains(tag)),
+            FieldQuery::Status(status) => bookmark.status() == *status,
+            FieldQuery::Behind(ordering, or_equal, number) => details.is_some_and(|details| {
+                let behind = bookmark.episodes_behind(details);
+                behind.cmp(number) == *ordering || (*or_equal && behind == *number)
+            }),
+            FieldQuery::Airs(start, end) => details
+                .and_then(|details| details.next_episode_to_air())
+                .and_then(|episode| episode.air_date)
+                .is_some_and(|date| *start <= date && date <= *end),
+        }
+    }
+}
+/// Word of the search input
+#[derive(Debug, Default)]
+struct Token {
+    text: String,
+    /// Written with a leading `-`
+    negated: bool,
+    /// Quoted as a whole, so it is never read as field
+    literal: bool,
+}
+/// Splits the input at whitespace. Double quotes group words into one token
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut token = Token::default();
+    let mut started = false;
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => {
+                if !started {
+                    token.literal = true;
+                }
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    tokens.push(std::mem::take(&mut token));
+                }
+                token = Token::default();
+                started = false;
+            }
+            '-' if !started && !token.negated => token.negated = true,
+            c => {
+                token.text.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        tokens.push(token);
+    }
+    tokens.retain(|token| !token.text.trim().is_empty());
+    tokens
+}
+/// Parsed search input. Free text is matched fuzzily against the names,
+/// terms like `tag:anime`, `status:onhold`, `behind:>3` or `airs:thisweek` filter by fields.
+/// Double quotes group words like `"the office"`, a leading `-` excludes the matches of a term.
+/// Unknown fields and invalid values are treated as free text
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    terms: Vec<Vec<char>>,
+    fields: Vec<FieldQuery>,
+    /// Names containing one of these are excluded
+    excluded_terms: Vec<Vec<char>>,
+    excluded_fields: Vec<FieldQuery>,
+}
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let today = calendar::today();
+        let mut query = Query::default();
+        for token in tokenize(input) {
+            let field = token
+                .text
+                .split_once(':')
+                .filter(|_| !token.literal)
+                .and_then(|(field, value)| FieldQuery::parse(&fold(field), value, today));
+            let term = || fold(&token.text).chars().collect();
+            match (field, token.negated) {
+                (Some(field), false) => query.fields.push(field),
+                (Some(field), true) => query.excluded_fields.push(field),
+                (None, false) => query.terms.push(term()),
+                (None, true) => query.excluded_terms.push(term()),
+            }
+        }
+        query
+    }
+    /// True if the query contains free text, by which the results are ranked
+    pub fn has_terms(&self) -> bool {
+        !self.terms.is_empty()
+    }
+    /// Returns the score of the bookmark or `None` if it doesn't match
+    pub fn score(&self, bookmark: &Bookmark, details: Option<&MovieDetails>) -> Option<i32> {
+        if !self
+            .fields
+            .iter()
+            .all(|field| field.matches(bookmark, details))
+            || self
+                .excluded_fields
+                .iter()
+                .any(|field| field.matches(bookmark, details))
+        {
+            return None;
+        }
+        let name = Folded::new(&bookmark.movie.name);
+        let original_name = Folded::new(&bookmark.movie.original_name);
+        // Excluded terms have to appear as they are, a fuzzy match would exclude too much
+        let contains = |text: &Folded, term: &Vec<char>| {
+            text.chars.windows(term.len()).any(|window| window == term)
+        };
+        if self.excluded_terms.iter().any(|term| {
+            [&name, &original_name]
+                .into_iter()
+                .any(|text| contains(text, term))
+        }) {
+            return None;
+        }
+        self.terms.iter().try_fold(0, |score, term| {
+            let best = [&name, &original_name]
+                .into_iter()
+                .filter_map(|text| fuzzy_match(term, text))
+                .map(|found| found.score)
+                .max()?;
+            Some(score + best)
+        })
+    }
+    /// Indices of the characters of `name` matched by the free text
+    pub fn highlights(&self, name: &str) -> Vec<usize> {
+        let name = Folded::new(name);
+        let mut positions: Vec<usize> = self
+            .terms
+            .iter()
+            .filter_map(|term| fuzzy_match(term, &name))
+            .flat_map(|found| found.positions)
+            .collect();
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+}
+impl State {
+    pub fn search_query(&self) -> Query {
+        Query::parse(&self.gui.input_caches[InputKind::SearchField])
+    }
+    /// Orders the bookmarks by how well they match the free text of the search.
+    /// Bookmarks with the same score keep their order
+    pub fn rank_by_search(&self, query: &Query, bookmarks: &mut [&Bookmark]) {
+        if !query.has_terms() {
+            return;
+        }
+        bookmarks.sort_by_cached_key(|bookmark| {
+            let details = self.movie_details.get(&bookmark.movie.id);
+            Reverse(query.score(bookmark, details))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::movie::test_movie;
+
+    fn bookmark(name: &str, original_name: &str, tags: &[&str]) -> Bookmark {
+        let mut movie = test_movie(1, name);
+        movie.original_name = original_name.to_owned();
+        let mut bookmark = Bookmark::from(&movie);
+        bookmark.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        bookmark
+    }
+    fn term(text: &str) -> Vec<char> {
+        fold(text).chars().collect()
+    }
+    fn matches(query: &str, bookmark: &Bookmark) -> bool {
+        Query::parse(query).score(bookmark, None).is_some()
+    }
+
+    #[test]
+    fn prefers_contiguous_matches_at_word_starts() {
+        let text = Folded::new("The Office");
+        let contiguous = fuzzy_match(&term("off"), &text).unwrap();
+        assert_eq!(contiguous.positions, [4, 5, 6]);
+        let scattered = fuzzy_match(&term("tof"), &text).unwrap();
+        assert_eq!(scattered.positions, [0, 4, 5]);
+        assert!(contiguous.score > scattered.score);
+        let inside = fuzzy_match(&term("ffi"), &text).unwrap();
+        assert!(contiguous.score > inside.score);
+        assert_eq!(fuzzy_match(&term("xyz"), &text), None);
+        assert_eq!(fuzzy_match(&term("eht"), &text), None);
+        assert_eq!(fuzzy_match(&[], &text).map(|found| found.score), Some(0));
+    }
+    #[test]
+    fn matches_without_case_and_diacritics() {
+        assert_eq!(fold("Pokémon ÉCOLE"), "pokemon ecole");
+        let pokemon = bookmark("Pokémon", "ポケットモンスター", &[]);
+        assert!(matches("pokemon", &pokemon));
+        assert!(matches("POKÉMON", &pokemon));
+        assert!(matches("ポケット", &pokemon));
+        // Highlights refer to the characters of the original name
+        assert_eq!(Query::parse("kem").highlights("Pokémon"), [2, 3, 4]);
+        assert_eq!(Query::parse("uber").highlights("Die Über"), [4, 5, 6, 7]);
+    }
+    #[test]
+    fn parses_field_prefixes() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 13).unwrap();
+        assert_eq!(
+            FieldQuery::parse("status", "on", today),
+            Some(FieldQuery::Status(WatchStatus::OnHold))
+        );
+        assert_eq!(
+            FieldQuery::parse("status", "plan-to", today),
+            Some(FieldQuery::Status(WatchStatus::PlanToWatch))
+        );
+        assert_eq!(FieldQuery::parse("status", "", today), None);
+        assert_eq!(
+            FieldQuery::parse("behind", ">=3", today),
+            Some(FieldQuery::Behind(Ordering::Greater, true, 3))
+        );
+        assert_eq!(
+            FieldQuery::parse("behind", "2", today),
+            Some(FieldQuery::Behind(Ordering::Equal, true, 2))
+        );
+        assert_eq!(FieldQuery::parse("behind", ">many", today), None);
+        let start = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 3, 17).unwrap();
+        assert_eq!(
+            FieldQuery::parse("airs", "ThisWeek", today),
+            Some(FieldQuery::Airs(start, end))
+        );
+        assert_eq!(FieldQuery::parse("airs", "someday", today), None);
+        assert_eq!(FieldQuery::parse("year", "2024", today), None);
+    }
+    #[test]
+    fn filters_by_fields() {
+        let anime = bookmark("Frieren", "Sousou no Frieren", &["Anime", "with Sam"]);
+        assert!(matches("tag:anime", &anime));
+        assert!(matches("TAG:sam frieren", &anime));
+        assert!(matches("status:watching", &anime));
+        assert!(!matches("status:completed", &anime));
+        assert!(!matches("tag:drama", &anime));
+        // Without details, nothing is known about the episodes
+        assert!(!matches("behind:>0", &anime));
+        // Unknown fields and invalid values are searched as text
+        let query = Query::parse("year:2024 status:");
+        assert!(query.fields.is_empty());
+        assert_eq!(query.terms, [term("year:2024"), term("status:")]);
+    }
+    #[test]
+    fn groups_quoted_words() {
+        let query = Query::parse(r#""the office" tag:"with sam" "tag:anime""#);
+        assert_eq!(query.terms, [term("the office"), term("tag:anime")]);
+        assert_eq!(query.fields, [FieldQuery::Tag("with sam".to_owned())]);
+        let anime = bookmark("Frieren", "Sousou no Frieren", &["with Sam"]);
+        assert!(matches(r#""no frieren""#, &anime));
+        assert!(!matches(r#""frieren no""#, &anime));
+        assert!(matches(r#"tag:"with   sam""#, &anime));
+        // An unterminated quote extends to the end
+        assert_eq!(Query::parse(r#""sousou no"#).terms, [term("sousou no")]);
+    }
+    #[test]
+    fn excludes_negated_terms() {
+        let office = bookmark("The Office", "The Office", &["Comedy"]);
+        assert!(!matches("-office", &office));
+        assert!(!matches("the -OFFICE", &office));
+        // Excluded terms are not matched fuzzily
+        assert!(matches("-oce", &office));
+        assert!(!matches(r#"-"the office""#, &office));
+        assert!(matches(r#"-"the offices""#, &office));
+        assert!(!matches("-tag:comedy", &office));
+        assert!(matches("-tag:drama", &office));
+        assert!(!matches("-status:watching", &office));
+        let query = Query::parse("-office -");
+        assert!(!query.has_terms());
+        assert_eq!(query.excluded_terms, [term("office")]);
+        // A dash inside a word doesn't negate it
+        let spider = bookmark("Spider-Man", "Spider-Man", &[]);
+        assert!(matches("spider-man", &spider));
+    }
+    #[test]
+    fn empty_queries_match_everything() {
+        let office = bookmark("The Office", "The Office", &[]);
+        for input in ["", "   ", "\"\"", "- \" \""] {
+            let query = Query::parse(input);
+            assert!(!query.has_terms(), "{input:?}");
+            assert_eq!(query.score(&office, None), Some(0), "{input:?}");
+            assert!(query.highlights("The Office").is_empty());
+        }
+    }
+}
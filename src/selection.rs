@@ -0,0 +1,373 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use iced::Command;
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::bookmark::{Bookmark, SyncMode, WatchStatus};
+use crate::id::{EpisodeId, MovieId};
+use crate::link::BookmarkLinks;
+use crate::message::Message;
+use crate::save::{self, SaveError};
+use crate::state::State;
+
+/// Change applied to all selected bookmarks at once
+#[derive(Debug, Clone, PartialEq)]
+pub enum BulkAction {
+    Delete,
+    SetStatus(WatchStatus),
+    AddTag(String),
+    RemoveTag(String),
+    ToggleSync,
+    /// Fetches the details again
+    Refresh,
+    /// Writes the bookmarks and their links as JSON
+    Export,
+}
+impl BulkAction {
+    /// Destructive actions are only applied after asking
+    pub fn needs_confirmation(&self) -> bool {
+        matches!(self, BulkAction::Delete)
+    }
+    /// Describes the action applied to `count` bookmarks
+    pub fn describe(&self, count: usize) -> String {
+        let bookmarks = if count == 1 { "bookmark" } else { "bookmarks" };
+        match self {
+            BulkAction::Delete => format!("Deleted {count} {bookmarks}"),
+            BulkAction::SetStatus(status) => format!("Moved {count} {bookmarks} to {status}"),
+            BulkAction::AddTag(tag) => format!("Tagged {count} {bookmarks} with {tag}"),
+            BulkAction::RemoveTag(tag) => format!("Removed {tag} from {count} {bookmarks}"),
+            BulkAction::ToggleSync => format!("Toggled the sync of {count} {bookmarks}"),
+            BulkAction::Refresh => format!("Refreshing {count} {bookmarks}"),
+            BulkAction::Export => format!("Exporting {count} {bookmarks}"),
+        }
+    }
+    /// Asks whether the action should be applied to `count` bookmarks
+    pub fn question(&self, count: usize) -> String {
+        let bookmarks = if count == 1 { "bookmark" } else { "bookmarks" };
+        match self {
+            BulkAction::Delete => {
+                format!("Delete {count} {bookmarks}? They can be restored from the archive")
+            }
+            action => format!("{}?", action.describe(count)),
+        }
+    }
+    /// True if the action changes the bookmarks, so it can be undone
+    fn modifies(&self) -> bool {
+        !matches!(self, BulkAction::Refresh | BulkAction::Export)
+    }
+}
+/// The bookmarks as they were before a bulk action
+#[derive(Debug, Clone)]
+pub struct Undo {
+    pub description: String,
+    action: BulkAction,
+    /// Changed bookmarks before the action with their position in the list
+    bookmarks: Vec<(usize, Bookmark)>,
+}
+/// Exported bookmarks, including their ratings and notes
+#[derive(Serialize)]
+struct BookmarkExport<'a> {
+    bookmarks: Vec<&'a Bookmark>,
+    links: HashMap<MovieId, &'a BookmarkLinks>,
+    /// Map entries like in the saved state, because JSON only supports strings as keys
+    episode_notes: Vec<(&'a EpisodeId, &'a String)>,
+}
+impl State {
+    /// Toggles the bookmark. With shift held, all bookmarks shown between the previously
+    /// toggled one and this one get the same state
+    pub fn toggle_selected(&mut self, id: MovieId) {
+        let select = !self.gui.selection.contains(&id);
+        let shown: Vec<MovieId> = self.shown_sections().concat();
+        let range = self
+            .gui
+            .selection_anchor
+            .filter(|_| self.gui.modifiers.shift())
+            .and_then(|anchor| {
+                let start = shown.iter().position(|shown| *shown == anchor)?;
+                let end = shown.iter().position(|shown| *shown == id)?;
+                Some(&shown[start.min(end)..=start.max(end)])
+            })
+            .unwrap_or(std::slice::from_ref(&id));
+        for id in range {
+            if select {
+                self.gui.selection.insert(*id);
+            } else {
+                self.gui.selection.remove(id);
+            }
+        }
+        self.gui.selection_anchor = Some(id);
+    }
+    pub fn select_all_shown(&mut self) {
+        self.gui.selection.extend(self.shown_sections().concat());
+    }
+    /// Ids of the selected bookmarks which are shown, in the order of the bookmarks.
+    /// Bookmarks hidden by the filter, the search or the tags are never changed
+    pub(crate) fn selected_ids(&self) -> Vec<MovieId> {
+        let shown: HashSet<MovieId> = self.shown_sections().concat().into_iter().collect();
+        self.bookmarks
+            .iter()
+            .map(|bookmark| bookmark.movie.id)
+            .filter(|id| self.gui.selection.contains(id) && shown.contains(id))
+            .collect()
+    }
+    /// Applies the action to all selected bookmarks, remembering how to undo it
+    pub fn apply_bulk(&mut self, action: BulkAction) -> Command<Message> {
+        self.apply_bulk_to(self.selected_ids(), action)
+    }
+    /// Applies the action to the bookmarks with the ids, remembering how to undo it
+    pub fn apply_bulk_to(&mut self, ids: Vec<MovieId>, action: BulkAction) -> Command<Message> {
+        if ids.is_empty() {
+            return Command::none();
+        }
+        let description = action.describe(ids.len());
+        info!("{description}");
+        if action.modifies() {
+            let selected: HashSet<MovieId> = ids.iter().copied().collect();
+            self.gui.undo = Some(Undo {
+                description,
+                action: action.clone(),
+                bookmarks: self
+                    .bookmarks
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, bookmark)| selected.contains(&bookmark.movie.id))
+                    .map(|(index, bookmark)| (index, bookmark.clone()))
+                    .collect(),
+            });
+        }
+        match action {
+            BulkAction::Delete => {
+                for id in ids {
+                    self.archive_bookmark(id);
+                }
+                self.gui.selection.clear();
+                Command::none()
+            }
+            BulkAction::Refresh => {
+                Command::batch(ids.into_iter().map(|id| self.refresh_details(id)))
+            }
+            BulkAction::Export => self.export_bookmarks(&ids, save::bookmarks_export_path()),
+            action => {
+                for bookmark in self
+                    .bookmarks
+                    .iter_mut()
+                    .filter(|bookmark| ids.contains(&bookmark.movie.id))
+                {
+                    match &action {
+                        BulkAction::SetStatus(status) => bookmark.set_status(*status),
+                        BulkAction::AddTag(tag) => {
+                            bookmark.tags.insert(tag.clone());
+                        }
+                        BulkAction::RemoveTag(tag) => {
+                            bookmark.tags.remove(tag);
+                        }
+                        BulkAction::ToggleSync => {
+                            bookmark.sync_mode = match bookmark.sync_mode {
+                                SyncMode::NoSync => SyncMode::Tmdb,
+                                SyncMode::Tmdb => SyncMode::NoSync,
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Command::none()
+            }
+        }
+    }
+    /// Reverts the last bulk action. Only what the action changed is reverted,
+    /// so later changes like the progress are kept
+    pub fn undo(&mut self) {
+        let Some(undo) = self.gui.undo.take() else {
+            return;
+        };
+        info!("Undo: {}", undo.description);
+        for (index, before) in undo.bookmarks {
+            let id = before.movie.id;
+            if undo.action == BulkAction::Delete {
+                // Deleted bookmarks are taken out of the archive again, unless they were
+                // restored or purged in the meantime
+                let Some(position) = self
+                    .archive
+                    .iter()
+                    .position(|archived| archived.bookmark.movie.id == id)
+                else {
+                    continue;
+                };
+                let bookmark = self.archive.remove(position).bookmark;
+                self.bookmarks
+                    .insert(index.min(self.bookmarks.len()), bookmark);
+                continue;
+            }
+            let Some(bookmark) = self.bookmarks.iter_mut().find(|b| b.movie.id == id) else {
+                continue;
+            };
+            match &undo.action {
+                BulkAction::SetStatus(_) => {
+                    bookmark.set_status(before.status());
+                    bookmark.finished = before.finished;
+                }
+                BulkAction::AddTag(tag) if !before.tags.contains(tag) => {
+                    bookmark.tags.remove(tag);
+                }
+                BulkAction::RemoveTag(tag) if before.tags.contains(tag) => {
+                    bookmark.tags.insert(tag.clone());
+                }
+                BulkAction::ToggleSync => bookmark.sync_mode = before.sync_mode,
+                _ => {}
+            }
+        }
+    }
+    /// Writes the bookmarks with the ids and their links as JSON file to `path`
+    fn export_bookmarks(&self, ids: &[MovieId], path: PathBuf) -> Command<Message> {
+        let export = BookmarkExport {
+            bookmarks: self
+                .bookmarks
+                .iter()
+                .filter(|bookmark| ids.contains(&bookmark.movie.id))
+                .collect(),
+            links: self
+                .links
+                .iter()
+                .filter(|(id, _)| ids.contains(id))
+                .map(|(id, links)| (*id, links))
+                .collect(),
+            episode_notes: self
+                .episode_notes
+                .iter()
+                .filter(|(id, _)| ids.contains(&id.0))
+                .collect(),
+        };
+        match serde_json::to_string_pretty(&export) {
+            Ok(json) => {
+                Command::perform(save::write_export(path, json), Message::BookmarksExported)
+            }
+            Err(e) => {
+                error!("failed to serialize the bookmarks: {e}");
+                Command::perform(async { Err(SaveError::Format) }, Message::BookmarksExported)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::keyboard::Modifiers;
+
+    use super::*;
+    use crate::movie::test_movie;
+
+    fn state(count: usize) -> State {
+        State {
+            bookmarks: (1..=count)
+                .map(|id| Bookmark::from(&test_movie(id, &format!("Show {id}"))))
+                .collect(),
+            ..State::default()
+        }
+    }
+    fn names(bookmarks: &[Bookmark]) -> Vec<&str> {
+        bookmarks.iter().map(|b| b.movie.name.as_str()).collect()
+    }
+    fn select(state: &mut State, indices: &[usize]) {
+        let ids: Vec<MovieId> = indices
+            .iter()
+            .map(|i| state.bookmarks[*i].movie.id)
+            .collect();
+        state.gui.selection.extend(ids);
+    }
+
+    #[test]
+    fn undoes_a_bulk_delete_at_the_old_positions() {
+        let mut state = state(3);
+        select(&mut state, &[0, 2]);
+        let _ = state.apply_bulk(BulkAction::Delete);
+        assert_eq!(names(&state.bookmarks), ["Show 2"]);
+        assert_eq!(state.archive.len(), 2);
+        assert!(state.gui.selection.is_empty());
+        state.undo();
+        assert_eq!(names(&state.bookmarks), ["Show 1", "Show 2", "Show 3"]);
+        assert!(state.archive.is_empty());
+        assert!(state.gui.undo.is_none());
+    }
+    #[test]
+    fn undo_skips_restored_and_purged_bookmarks() {
+        let mut state = state(3);
+        select(&mut state, &[0, 1]);
+        let ids: Vec<MovieId> = state.bookmarks.iter().map(|b| b.movie.id).collect();
+        let _ = state.apply_bulk(BulkAction::Delete);
+        assert!(state.restore_bookmark(ids[0]).is_some());
+        let _ = state.purge(&[ids[1]]);
+        state.undo();
+        assert_eq!(names(&state.bookmarks), ["Show 3", "Show 1"]);
+        assert!(state.archive.is_empty());
+    }
+    #[test]
+    fn undo_keeps_tags_which_were_there_before() {
+        let mut state = state(2);
+        state.bookmarks[0].tags.insert("anime".to_owned());
+        select(&mut state, &[0, 1]);
+        let _ = state.apply_bulk(BulkAction::AddTag("anime".to_owned()));
+        assert!(state.bookmarks.iter().all(|b| b.tags.contains("anime")));
+        state.undo();
+        assert!(state.bookmarks[0].tags.contains("anime"));
+        assert!(!state.bookmarks[1].tags.contains("anime"));
+
+        let _ = state.apply_bulk(BulkAction::RemoveTag("anime".to_owned()));
+        assert!(state.bookmarks.iter().all(|b| b.tags.is_empty()));
+        // Changes after the action are kept
+        state.bookmarks[1].tags.insert("drama".to_owned());
+        state.undo();
+        assert!(state.bookmarks[0].tags.contains("anime"));
+        assert_eq!(state.bookmarks[1].tags.len(), 1);
+        assert!(state.bookmarks[1].tags.contains("drama"));
+    }
+    #[test]
+    fn undoes_status_changes() {
+        let mut state = state(2);
+        state.bookmarks[1].set_finished(true);
+        // Completed bookmarks aren't shown with the default filter, so they can't be selected
+        let ids = state.bookmarks.iter().map(|b| b.movie.id).collect();
+        let _ = state.apply_bulk_to(ids, BulkAction::SetStatus(WatchStatus::OnHold));
+        assert!(state
+            .bookmarks
+            .iter()
+            .all(|b| b.status() == WatchStatus::OnHold));
+        state.undo();
+        assert_eq!(state.bookmarks[0].status(), WatchStatus::Watching);
+        assert_eq!(state.bookmarks[1].status(), WatchStatus::Completed);
+        assert!(state.bookmarks[1].finished);
+    }
+    #[test]
+    fn selects_shift_ranges_across_sections() {
+        let mut state = state(4);
+        for index in [1, 3] {
+            let bookmark = &mut state.bookmarks[index];
+            bookmark.rewatch = Some(bookmark.current_episode.clone());
+        }
+        let sections = state.shown_sections();
+        assert_eq!(sections.len(), 2);
+        let shown = sections.concat();
+        state.toggle_selected(shown[1]);
+        state.gui.modifiers = Modifiers::SHIFT;
+        state.toggle_selected(shown[2]);
+        assert_eq!(
+            state.gui.selection,
+            HashSet::from([shown[1], shown[2]]),
+            "the range spans the end of the first and the start of the second section"
+        );
+        assert!(sections[0].contains(&shown[1]) && sections[1].contains(&shown[2]));
+        state.toggle_selected(shown[0]);
+        assert_eq!(
+            state.gui.selection,
+            HashSet::from([shown[0], shown[1], shown[2]])
+        );
+        // Shift-clicking a selected bookmark deselects the range up to it
+        state.toggle_selected(shown[1]);
+        assert_eq!(state.gui.selection, HashSet::from([shown[2]]));
+        // Without shift only the clicked bookmark changes
+        state.gui.modifiers = Modifiers::empty();
+        state.toggle_selected(shown[3]);
+        assert_eq!(state.gui.selection, HashSet::from([shown[2], shown[3]]));
+    }
+}
@@ -0,0 +1,236 @@
+use std::cmp::Reverse;
+use std::fmt::Display;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::bookmark::Bookmark;
+use crate::id::MovieId;
+use crate::movie::TmdbMovie;
+use crate::state::State;
+
+/// Order in which bookmarks and search results are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortOrder {
+    /// The order of `State::bookmarks`, which can be changed by dragging
+    #[default]
+    Manual,
+    Name,
+    /// Most recently watched first
+    LastWatched,
+    /// Soonest airing first, bookmarks without upcoming episodes last
+    NextAirDate,
+    /// Most episodes behind first
+    EpisodesBehind,
+    /// Highest TMDB rating first
+    Rating,
+    /// Newest first
+    DateAdded,
+}
+impl SortOrder {
+    pub const ALL: [SortOrder; 7] = [
+        SortOrder::Manual,
+        SortOrder::Name,
+        SortOrder::LastWatched,
+        SortOrder::NextAirDate,
+        SortOrder::EpisodesBehind,
+        SortOrder::Rating,
+        SortOrder::DateAdded,
+    ];
+}
+impl Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SortOrder::Manual => "Manual",
+            SortOrder::Name => "Name",
+            SortOrder::LastWatched => "Last watched",
+            SortOrder::NextAirDate => "Next air date",
+            SortOrder::EpisodesBehind => "Episodes behind",
+            SortOrder::Rating => "Rating",
+            SortOrder::DateAdded => "Date added",
+        };
+        write!(f, "{name}")
+    }
+}
+impl State {
+    /// Sorts by the configured order. The sort is stable, so ties keep the manual order
+    pub fn sort_bookmarks(&self, bookmarks: &mut [&Bookmark]) {
+        match self.config.sort {
+            SortOrder::Manual => {}
+            SortOrder::Name => {
+                bookmarks.sort_by_cached_key(|bookmark| bookmark.movie.name.to_lowercase())
+            }
+            SortOrder::LastWatched => {
+                bookmarks.sort_by_key(|bookmark| Reverse(bookmark.last_watched))
+            }
+            SortOrder::NextAirDate => bookmarks.sort_by_key(|bookmark| {
+                let date = self.next_air_date(bookmark.movie.id);
+                (date.is_none(), date)
+            }),
+            SortOrder::EpisodesBehind => bookmarks.sort_by_key(|bookmark| {
+                let behind = self
+                    .movie_details
+                    .get(&bookmark.movie.id)
+                    .map(|details| bookmark.episodes_behind(details));
+                Reverse(behind)
+            }),
+            SortOrder::Rating => bookmarks.sort_by_key(|bookmark| Reverse(bookmark.movie.rating())),
+            SortOrder::DateAdded => bookmarks.sort_by_key(|bookmark| Reverse(bookmark.added)),
+        }
+    }
+    /// Search results in the configured order.
+    /// Orders which depend on bookmark data use the bookmark of a result if there is one,
+    /// otherwise the relevance order of TMDB is kept
+    pub fn sorted_movies(&self) -> Vec<&TmdbMovie> {
+        let mut movies: Vec<&TmdbMovie> = self.movies.iter().collect();
+        match self.config.sort {
+            SortOrder::Manual => {}
+            SortOrder::Name => movies.sort_by_cached_key(|movie| movie.name.to_lowercase()),
+            SortOrder::Rating => movies.sort_by_key(|movie| Reverse(movie.rating())),
+            SortOrder::LastWatched
+            | SortOrder::NextAirDate
+            | SortOrder::EpisodesBehind
+            | SortOrder::DateAdded => {
+                let mut bookmarks: Vec<&Bookmark> = movies
+                    .iter()
+                    .filter_map(|movie| self.bookmarks.iter().find(|b| b.movie.id == movie.id))
+                    .collect();
+                self.sort_bookmarks(&mut bookmarks);
+                // Bookmarked results come first in the sorted order
+                movies.sort_by_key(|movie| {
+                    bookmarks
+                        .iter()
+                        .position(|bookmark| bookmark.movie.id == movie.id)
+                        .unwrap_or(usize::MAX)
+                });
+            }
+        }
+        movies
+    }
+    fn next_air_date(&self, id: MovieId) -> Option<NaiveDate> {
+        self.movie_details
+            .get(&id)
+            .and_then(|details| details.next_episode_to_air())
+            .and_then(|episode| episode.air_date)
+    }
+    /// Moves the dragged bookmark to the position of `target` in the manual order
+    pub fn move_bookmark(&mut self, dragged: MovieId, target: MovieId) {
+        let position = |id| {
+            self.bookmarks
+                .iter()
+                .position(|bookmark| bookmark.movie.id == id)
+        };
+        if let (Some(from), Some(to)) = (position(dragged), position(target)) {
+            let bookmark = self.bookmarks.remove(from);
+            self.bookmarks.insert(to, bookmark);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::movie::test_movie;
+    use crate::movie_details::test_details;
+
+    fn state(names: &[&str]) -> State {
+        State {
+            bookmarks: names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| Bookmark::from(&test_movie(i + 1, name)))
+                .collect(),
+            ..State::default()
+        }
+    }
+    fn sorted(state: &mut State, sort: SortOrder) -> Vec<usize> {
+        state.config.sort = sort;
+        let mut bookmarks: Vec<&Bookmark> = state.bookmarks.iter().collect();
+        state.sort_bookmarks(&mut bookmarks);
+        bookmarks
+            .iter()
+            .map(|bookmark| bookmark.movie.id.id())
+            .collect()
+    }
+    fn order(state: &State) -> Vec<usize> {
+        state
+            .bookmarks
+            .iter()
+            .map(|bookmark| bookmark.movie.id.id())
+            .collect()
+    }
+    fn id(state: &State, index: usize) -> MovieId {
+        state.bookmarks[index].movie.id
+    }
+
+    #[test]
+    fn ties_keep_the_manual_order() {
+        let mut state = state(&["b", "A", "c", "a"]);
+        state.bookmarks[2].last_watched = Utc.timestamp_opt(1_000, 0).single();
+        assert_eq!(sorted(&mut state, SortOrder::Manual), [1, 2, 3, 4]);
+        assert_eq!(sorted(&mut state, SortOrder::Name), [2, 4, 1, 3]);
+        assert_eq!(sorted(&mut state, SortOrder::LastWatched), [3, 1, 2, 4]);
+        // All test movies have the same rating
+        assert_eq!(sorted(&mut state, SortOrder::Rating), [1, 2, 3, 4]);
+        // Without details nobody is behind
+        assert_eq!(sorted(&mut state, SortOrder::EpisodesBehind), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn bookmarks_without_air_date_come_last() {
+        let mut state = state(&["a", "b", "c", "d"]);
+        let date = |day| NaiveDate::from_ymd_opt(2024, 3, day);
+        // The first bookmark has no details at all
+        for (index, next_air_date) in [(1, date(20)), (2, None), (3, date(10))] {
+            let details = test_details(index + 1, &[10], (1, 5), next_air_date);
+            state.movie_details.insert(id(&state, index), details);
+        }
+        assert_eq!(sorted(&mut state, SortOrder::NextAirDate), [4, 2, 1, 3]);
+    }
+
+    #[test]
+    fn bookmarked_search_results_come_first() {
+        let mut state = state(&["a", "b"]);
+        state.bookmarks[0].last_watched = Utc.timestamp_opt(1_000, 0).single();
+        state.bookmarks[1].last_watched = Utc.timestamp_opt(2_000, 0).single();
+        state.movies = vec![
+            test_movie(3, "c"),
+            test_movie(1, "a"),
+            test_movie(4, "d"),
+            test_movie(2, "b"),
+        ];
+        let sorted_movies = |state: &State| -> Vec<usize> {
+            state
+                .sorted_movies()
+                .iter()
+                .map(|movie| movie.id.id())
+                .collect()
+        };
+        // TMDB relevance order
+        assert_eq!(sorted_movies(&state), [3, 1, 4, 2]);
+        state.config.sort = SortOrder::LastWatched;
+        assert_eq!(sorted_movies(&state), [2, 1, 3, 4]);
+        state.config.sort = SortOrder::Name;
+        assert_eq!(sorted_movies(&state), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn moves_bookmarks_to_the_target_position() {
+        let mut state = state(&["a", "b", "c", "d"]);
+        // Downwards the dragged bookmark comes after the target
+        state.move_bookmark(id(&state, 0), id(&state, 2));
+        assert_eq!(order(&state), [2, 3, 1, 4]);
+        // Upwards before it
+        state.move_bookmark(id(&state, 3), id(&state, 0));
+        assert_eq!(order(&state), [4, 2, 3, 1]);
+        state.move_bookmark(id(&state, 1), id(&state, 1));
+        assert_eq!(order(&state), [4, 2, 3, 1]);
+        // Unknown bookmarks change nothing
+        let unknown = test_movie(9, "z").id;
+        state.move_bookmark(unknown, id(&state, 0));
+        state.move_bookmark(id(&state, 0), unknown);
+        assert_eq!(order(&state), [4, 2, 3, 1]);
+    }
+}
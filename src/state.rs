@@ -1,16 +1,30 @@
-use iced::Command;
+use iced::widget::text_editor;
+use iced::{keyboard, Command, Theme};
 use tracing::debug;
 
+use crate::archive::{ArchiveConfirmation, ArchivedBookmark};
+use crate::calendar::CalendarRange;
+use crate::collection::Collection;
+use crate::config::{Config, Setting};
 use crate::filter::Filter;
 use crate::id::{EpisodeId, MovieId};
-use crate::link::Link;
-use crate::message::{Message, ShiftPressed};
+use crate::keymap::Keymap;
+use crate::launch;
+use crate::library::LocalLibrary;
+use crate::link::{BookmarkLinks, LinkAction, LinkActionKind};
+use crate::message::Message;
 use crate::save::SavedState;
-use std::collections::HashMap;
+use crate::selection::{BulkAction, Undo};
+use crate::stats::{StatsRange, WatchEvent};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::ops::{Index, IndexMut};
+use std::time::SystemTime;
 
 use crate::movie::TmdbMovie;
-use crate::movie_details::{EpisodeDetails, MovieDetails};
+use crate::movie_details::{Episode, EpisodeDetails, MovieDetails, SeasonDetails};
+use crate::notify::{Notification, NotifierHandle};
+use crate::theme::CustomTheme;
+use crate::tmdb::{self, RequestType};
 
 use crate::bookmark::{Bookmark, Poster};
 #[derive(Clone, Copy, Debug, PartialEq, Hash, Eq)]
@@ -19,6 +33,13 @@ pub enum InputKind {
     EpisodeInput,
     SeasonInput,
     LinkInput,
+    CalendarPath,
+    LinkNameInput,
+    LinkCommandInput,
+    LocalDirInput,
+    TagInput,
+    CollectionNameInput,
+    BulkTagInput,
 }
 impl InputKind {
     pub fn index(&self) -> usize {
@@ -27,11 +48,18 @@ impl InputKind {
             InputKind::EpisodeInput => 1,
             InputKind::SeasonInput => 2,
             InputKind::LinkInput => 3,
+            InputKind::CalendarPath => 4,
+            InputKind::LinkNameInput => 5,
+            InputKind::LinkCommandInput => 6,
+            InputKind::LocalDirInput => 7,
+            InputKind::TagInput => 8,
+            InputKind::CollectionNameInput => 9,
+            InputKind::BulkTagInput => 10,
         }
     }
 }
 #[derive(Clone, Debug, Default)]
-pub struct InputCaches([String; 4]);
+pub struct InputCaches([String; 11]);
 
 impl IndexMut<InputKind> for InputCaches {
     fn index_mut(&mut self, index: InputKind) -> &mut Self::Output {
@@ -52,7 +80,58 @@ pub struct GuiState {
     pub filter: Filter,
     pub dirty: bool,
     pub saving: bool,
-    pub shift_pressed: ShiftPressed,
+    pub calendar_range: CalendarRange,
+    /// Outcome of the last calendar export
+    pub calendar_export: Option<String>,
+    /// The calendar subscription is being written, further changes wait until it is done
+    pub calendar_writing: bool,
+    /// The calendar subscription has changes which are not written yet
+    pub calendar_dirty: bool,
+    /// Index of the link of the shown bookmark which is edited. `None` if a new link is added
+    pub editing_link: Option<usize>,
+    /// Action selected in the link edit form
+    pub link_action: LinkActionKind,
+    /// Currently pressed modifiers, used to override link actions
+    pub modifiers: keyboard::Modifiers,
+    /// Episodes for which is asked whether they were watched, at most one per bookmark
+    pub advance_prompts: Vec<(MovieId, Episode)>,
+    /// Inputs of the settings page, which may not be valid yet
+    pub setting_inputs: HashMap<Setting, String>,
+    /// Why the input of a setting couldn't be applied
+    pub setting_errors: HashMap<Setting, String>,
+    /// Current width of the window, used to fit the bookmark grid
+    pub window_width: f32,
+    /// Bookmark which is dragged to change the manual order
+    pub dragging: Option<MovieId>,
+    /// Only bookmarks with all of these tags are shown
+    pub tag_filter: BTreeSet<String>,
+    /// Notes of the bookmark shown in the details
+    pub notes_editor: text_editor::Content,
+    /// Grouping of the episodes chart on the statistics page
+    pub stats_range: StatsRange,
+    /// Outcome of the last statistics export
+    pub stats_export: Option<String>,
+    /// Bookmark selected with the keyboard
+    pub focused: Option<MovieId>,
+    /// Filter to return to when leaving the details
+    pub previous_filter: Filter,
+    /// True while the list of shortcuts is shown
+    pub show_keymap: bool,
+    /// True while bookmarks can be selected for bulk actions
+    pub selecting: bool,
+    pub selection: HashSet<MovieId>,
+    /// Bookmark toggled last, from which shift-clicks select a range
+    pub selection_anchor: Option<MovieId>,
+    /// Bulk action waiting for confirmation
+    pub pending_bulk: Option<BulkAction>,
+    /// How to revert the last bulk action
+    pub undo: Option<Undo>,
+    /// Outcome of the last bookmark export
+    pub bookmarks_export: Option<String>,
+    /// Deletion or purge waiting for confirmation
+    pub archive_confirmation: Option<ArchiveConfirmation>,
+    /// Outcome of the last purge
+    pub purge_info: Option<String>,
 }
 #[derive(Debug, Default)]
 pub struct State {
@@ -61,8 +140,45 @@ pub struct State {
     pub movie_details: HashMap<MovieId, MovieDetails>,
     pub movie_posters: HashMap<MovieId, Poster>,
     pub episode_details: HashMap<EpisodeId, EpisodeDetails>,
-    pub links: HashMap<MovieId, Link>,
+    /// Details of the season containing the next episode to air
+    pub season_details: HashMap<MovieId, SeasonDetails>,
+    pub links: HashMap<MovieId, BookmarkLinks>,
     pub bookmarks: Vec<Bookmark>,
+    /// Bookmarks which gained episodes since the previous run together with the number of new episodes
+    pub new_since_launch: Vec<(MovieId, usize)>,
+    pub config: Config,
+    pub collections: Vec<Collection>,
+    /// Personal notes on single episodes, like where watching stopped
+    pub episode_notes: HashMap<EpisodeId, String>,
+    /// Every advance of a bookmark, the oldest first
+    pub watch_log: Vec<WatchEvent>,
+    /// Deleted bookmarks which can be restored
+    pub archive: Vec<ArchivedBookmark>,
+    /// Time at which the details of a movie were received the last time
+    pub details_refreshed: HashMap<MovieId, SystemTime>,
+    /// Time at which the periodic refresh requested the details of a movie the last time.
+    /// A bookmark whose requests keep failing waits for the next interval like the others
+    pub refresh_attempted: HashMap<MovieId, SystemTime>,
+    /// Movies whose refresh request is still running
+    pub refreshing: HashSet<MovieId>,
+    /// Entity tags of the details responses used for conditional requests
+    pub details_etags: HashMap<MovieId, String>,
+    /// History of notifications, the newest first
+    pub notifications: Vec<Notification>,
+    pub notifier: NotifierHandle,
+    /// Episodes found in the local directories of the bookmarks, or why scanning failed
+    pub libraries: HashMap<MovieId, Result<LocalLibrary, String>>,
+    /// Palettes found in the themes directory
+    pub custom_themes: Vec<CustomTheme>,
+    /// True if the system prefers a dark theme
+    pub system_dark: bool,
+    /// The active theme resolved from the config
+    pub theme: Theme,
+    /// The config as it is in the config file, `None` if there is no config file yet
+    pub saved_config: Option<Config>,
+    pub keymap: Keymap,
+    /// Why the keymap file couldn't be applied
+    pub keymap_error: Option<String>,
 }
 impl State {
     pub fn save(&mut self, saved: bool) -> Command<Message> {
@@ -74,18 +190,125 @@ impl State {
             self.gui.dirty = false;
             self.gui.saving = true;
             debug!("saving state");
+            let write_config = self.saved_config.as_ref() != Some(&self.config);
+            if write_config {
+                self.saved_config = Some(self.config.clone());
+            }
             Command::perform(
                 SavedState {
                     bookmarks: self.bookmarks.clone(),
                     links: self.links.clone(),
+                    config: self.config.clone(),
+                    notifications: self.notifications.clone(),
+                    collections: self.collections.clone(),
+                    episode_notes: self
+                        .episode_notes
+                        .iter()
+                        .map(|(id, note)| (id.clone(), note.clone()))
+                        .collect(),
+                    watch_log: self.watch_log.clone(),
+                    archive: self.archive.clone(),
                 }
-                .save(),
+                .save(write_config),
                 Message::Saved,
             )
         } else {
             Command::none()
         }
     }
+    /// Bookmarks with the status of the current filter, which match the search input.
+    /// Rewatched bookmarks are excluded from the watching list as they are shown in their own
+    /// section there
+    pub fn filtered_bookmarks(&self) -> Vec<&Bookmark> {
+        let mut bookmarks: Vec<&Bookmark> = if let Filter::Collection(index) = self.gui.filter {
+            let Some(collection) = self.collections.get(index) else {
+                return Vec::new();
+            };
+            self.searched_bookmarks()
+                .filter(|bookmark| collection.contains(bookmark))
+                .collect()
+        } else {
+            let Some(status) = self.gui.filter.watch_status() else {
+                return Vec::new();
+            };
+            let own_section = self.gui.filter == Filter::Bookmarks;
+            self.searched_bookmarks()
+                .filter(|bookmark| !(own_section && bookmark.is_rewatching()))
+                .filter(|bookmark| bookmark.status() == status)
+                .collect()
+        };
+        self.sort_bookmarks(&mut bookmarks);
+        self.rank_by_search(&self.search_query(), &mut bookmarks);
+        bookmarks
+    }
+    /// Bookmarks which are currently rewatched and match the search input
+    pub fn rewatching_bookmarks(&self) -> Vec<&Bookmark> {
+        let mut bookmarks: Vec<&Bookmark> = self
+            .searched_bookmarks()
+            .filter(|bookmark| bookmark.is_rewatching())
+            .collect();
+        self.sort_bookmarks(&mut bookmarks);
+        self.rank_by_search(&self.search_query(), &mut bookmarks);
+        bookmarks
+    }
+    /// Ids of the bookmarks in the order they are shown, split into the sections of the page
+    pub(crate) fn shown_sections(&self) -> Vec<Vec<MovieId>> {
+        if !self.gui.filter.shows_bookmarks() {
+            return Vec::new();
+        }
+        let ids = |bookmarks: Vec<&Bookmark>| {
+            bookmarks.iter().map(|bookmark| bookmark.movie.id).collect()
+        };
+        let mut sections = vec![ids(self.filtered_bookmarks())];
+        if self.gui.filter == Filter::Bookmarks {
+            sections.push(ids(self.rewatching_bookmarks()));
+        }
+        sections.retain(|section: &Vec<MovieId>| !section.is_empty());
+        sections
+    }
+    fn searched_bookmarks(&self) -> impl Iterator<Item = &Bookmark> {
+        let query = self.search_query();
+        self.bookmarks
+            .iter()
+            .filter(move |bookmark| {
+                let details = self.movie_details.get(&bookmark.movie.id);
+                query.score(bookmark, details).is_some()
+            })
+            .filter(|bookmark| self.gui.tag_filter.is_subset(&bookmark.tags))
+    }
+    /// The bookmark whose details were refreshed or tried to be refreshed the longest time ago,
+    /// if that is longer ago than the refresh interval. Bookmarks which are being refreshed are skipped
+    pub fn next_refresh(&self, now: SystemTime) -> Option<MovieId> {
+        let interval = self.config.refresh_interval();
+        self.bookmarks
+            .iter()
+            .map(|bookmark| bookmark.movie.id)
+            .filter(|id| !self.refreshing.contains(id))
+            .map(|id| {
+                let time = |times: &HashMap<MovieId, SystemTime>| times.get(&id).copied();
+                let refreshed = time(&self.details_refreshed)
+                    .max(time(&self.refresh_attempted))
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                (id, refreshed)
+            })
+            .filter(|(_, refreshed)| {
+                now.duration_since(*refreshed)
+                    .is_ok_and(|elapsed| elapsed >= interval)
+            })
+            .min_by_key(|(_, refreshed)| *refreshed)
+            .map(|(id, _)| id)
+    }
+    /// Requests the details of the bookmark again, unless they didn't change
+    pub fn refresh_details(&self, id: MovieId) -> Command<Message> {
+        debug!("refresh details of {id}");
+        let etag = self.details_etags.get(&id).cloned();
+        let request = RequestType::TvDetails { id };
+        let language = self.config.language.clone();
+        Command::perform(
+            tmdb::send_cached_request(request, etag, language),
+            move |data| Message::Refreshed(id, data.ok()),
+        )
+    }
     pub fn get_bookmark(&self, movie_id: MovieId) -> Option<&Bookmark> {
         self.bookmarks
             .iter()
@@ -95,14 +318,78 @@ impl State {
         let Some(bookmark) = self.get_bookmark(movie_id) else {
             return;
         };
-        let episode = bookmark.current_episode.clone();
-        let link = self
-            .links
-            .get(&movie_id)
-            .map(|link| link.string_link.clone())
+        let local_dir = bookmark
+            .local_dir
+            .as_ref()
+            .map(|dir| dir.display().to_string())
             .unwrap_or_default();
+        self.gui.notes_editor = text_editor::Content::with_text(&bookmark.notes);
+        self.gui.input_caches[InputKind::LocalDirInput] = local_dir;
+        self.gui.input_caches[InputKind::TagInput] = String::new();
+        self.set_progress_input_caches(movie_id);
+        self.set_link_input_caches(movie_id, None);
+    }
+    /// Fills the episode and season inputs with the progress of the bookmark
+    pub(crate) fn set_progress_input_caches(&mut self, movie_id: MovieId) {
+        let Some(bookmark) = self.get_bookmark(movie_id) else {
+            return;
+        };
+        let episode = bookmark.current_episode.clone();
         self.gui.input_caches[InputKind::EpisodeInput] = episode.episode().to_string();
         self.gui.input_caches[InputKind::SeasonInput] = episode.season().to_string();
-        self.gui.input_caches[InputKind::LinkInput] = link;
+    }
+    /// Fills the link inputs with the link at `index` to edit it or clears them for a new link
+    pub(crate) fn set_link_input_caches(&mut self, movie_id: MovieId, index: Option<usize>) {
+        let link = index.and_then(|index| self.links.get(&movie_id)?.links.get(index));
+        let (name, string_link, action) = link
+            .map(|link| {
+                (
+                    link.name.clone(),
+                    link.link.string_link.clone(),
+                    link.action.clone(),
+                )
+            })
+            .unwrap_or_default();
+        self.gui.input_caches[InputKind::LinkNameInput] = name;
+        self.gui.input_caches[InputKind::LinkInput] = string_link;
+        self.gui.input_caches[InputKind::LinkCommandInput] = match &action {
+            LinkAction::Command(argv) => launch::join_arguments(argv),
+            _ => String::new(),
+        };
+        self.gui.link_action = action.kind();
+        self.gui.editing_link = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movie::test_movie;
+
+    #[test]
+    fn failing_refreshes_dont_hold_back_other_bookmarks() {
+        let mut state = State {
+            bookmarks: (1..=3)
+                .map(|id| Bookmark::from(&test_movie(id, "Show")))
+                .collect(),
+            ..State::default()
+        };
+        let ids: Vec<MovieId> = state.bookmarks.iter().map(|b| b.movie.id).collect();
+        let now = SystemTime::now();
+        state.details_refreshed.insert(ids[2], now);
+        let _ = state.update_state(Message::RefreshTick);
+        assert!(state.refreshing.contains(&ids[0]));
+        // The request is still running
+        assert_eq!(state.next_refresh(now), Some(ids[1]));
+        let _ = state.update_state(Message::Refreshed(ids[0], None));
+        assert!(state.refreshing.is_empty());
+        let _ = state.update_state(Message::RefreshTick);
+        assert!(state.refreshing.contains(&ids[1]));
+        let _ = state.update_state(Message::Refreshed(ids[1], None));
+        // Everything was tried within the interval
+        assert_eq!(state.next_refresh(SystemTime::now()), None);
+        // Then the one refreshed the longest time ago comes first
+        let later = SystemTime::now() + state.config.refresh_interval();
+        assert_eq!(state.next_refresh(later), Some(ids[2]));
     }
 }
@@ -0,0 +1,425 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, Utc};
+use iced::Command;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::bookmark::{Bookmark, SyncMode, WatchStatus};
+use crate::calendar;
+use crate::id::{EpisodeId, MovieId};
+use crate::message::Message;
+use crate::movie_details::{Episode, TotalEpisode};
+use crate::save::{self, SaveError};
+use crate::state::State;
+
+/// Number of weeks or months shown in the chart
+const CHART_PERIODS: usize = 12;
+
+/// Entry of the watch log, written whenever a bookmark advances
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchEvent {
+    pub id: MovieId,
+    /// The episode which was watched
+    pub episode: Episode,
+    pub time: DateTime<Utc>,
+    /// Runtime of the episode in minutes, if it was known at the time
+    #[serde(default)]
+    pub runtime: Option<usize>,
+}
+/// Position of a bookmark, taken before it changes to find the newly watched episodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub episode: EpisodeId,
+    /// True if `episode` is already watched
+    pub finished: bool,
+}
+/// Grouping of the episodes chart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatsRange {
+    #[default]
+    Weeks,
+    Months,
+}
+impl Display for StatsRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatsRange::Weeks => write!(f, "Weeks"),
+            StatsRange::Months => write!(f, "Months"),
+        }
+    }
+}
+/// Episodes watched in the period starting at `start`
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodCount {
+    pub start: NaiveDate,
+    pub episodes: usize,
+}
+/// Summary of the watch log and the bookmarks
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    pub episodes_watched: usize,
+    /// Estimated from the runtimes reported by TMDB
+    pub watch_time_minutes: usize,
+    /// Watched episodes without known runtime, which are missing in the watch time
+    pub episodes_without_runtime: usize,
+    pub episodes_per_week: Vec<PeriodCount>,
+    pub episodes_per_month: Vec<PeriodCount>,
+    /// Genres by watched episodes, most watched first
+    pub genres: Vec<(String, usize)>,
+    /// Fraction of the started bookmarks which are completed
+    pub completion_rate: Option<f32>,
+    /// Most consecutive days with at least one watched episode
+    pub longest_streak_days: usize,
+    /// Consecutive days with watched episodes up to today or yesterday
+    pub current_streak_days: usize,
+    /// Aired episodes which are not watched yet in bookmarks which are watched
+    pub backlog_episodes: usize,
+    pub backlog_shows: usize,
+}
+/// Name of a TMDB genre
+pub fn genre_name(id: usize) -> Option<&'static str> {
+    let name = match id {
+        10759 => "Action & Adventure",
+        16 => "Animation",
+        35 => "Comedy",
+        80 => "Crime",
+        99 => "Documentary",
+        18 => "Drama",
+        10751 => "Family",
+        10762 => "Kids",
+        9648 => "Mystery",
+        10763 => "News",
+        10764 => "Reality",
+        10765 => "Sci-Fi & Fantasy",
+        10766 => "Soap",
+        10767 => "Talk",
+        10768 => "War & Politics",
+        37 => "Western",
+        28 => "Action",
+        12 => "Adventure",
+        14 => "Fantasy",
+        36 => "History",
+        27 => "Horror",
+        10402 => "Music",
+        10749 => "Romance",
+        878 => "Science Fiction",
+        10770 => "TV Movie",
+        53 => "Thriller",
+        10752 => "War",
+        _ => return None,
+    };
+    Some(name)
+}
+/// Counts the dates per period of `CHART_PERIODS` periods ending with the one containing `today`
+fn count_periods(dates: &[NaiveDate], today: NaiveDate, range: StatsRange) -> Vec<PeriodCount> {
+    let period_start = |date: NaiveDate| match range {
+        StatsRange::Weeks => date.week(chrono::Weekday::Mon).first_day(),
+        StatsRange::Months => date.with_day(1).unwrap_or(date),
+    };
+    let current = period_start(today);
+    (0..CHART_PERIODS)
+        .rev()
+        .filter_map(|ago| match range {
+            StatsRange::Weeks => current.checked_sub_days(Days::new(7 * ago as u64)),
+            StatsRange::Months => current.checked_sub_months(Months::new(ago as u32)),
+        })
+        .map(|start| PeriodCount {
+            start,
+            episodes: dates
+                .iter()
+                .filter(|date| period_start(**date) == start)
+                .count(),
+        })
+        .collect()
+}
+/// Returns the longest and the current streak of consecutive days
+fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (usize, usize) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in days {
+        run = match previous {
+            Some(previous) if previous.succ_opt() == Some(*day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+    let yesterday = today.pred_opt().unwrap_or(today);
+    let current = match previous {
+        Some(last) if last == today || last == yesterday => run,
+        _ => 0,
+    };
+    (longest, current)
+}
+impl State {
+    /// Adds the episodes the bookmark advanced past to the watch log.
+    /// `before` is the progress from before the bookmark was updated.
+    /// Episodes which were skipped, e.g. by finishing the season, are logged as well
+    /// if the details of synced bookmarks are known
+    pub fn log_if_advanced(&mut self, before: Option<Progress>) {
+        let Some(before) = before else {
+            return;
+        };
+        let id = before.episode.0;
+        let Some(after) = self.get_bookmark(id).map(Bookmark::progress) else {
+            return;
+        };
+        let time = Utc::now();
+        for episode in self.passed_episodes(&before, &after) {
+            let episode_id = EpisodeId(id, episode);
+            let runtime = self
+                .episode_details
+                .get(&episode_id)
+                .and_then(|details| details.runtime)
+                .or_else(|| self.movie_details.get(&id)?.episode_runtime());
+            self.watch_log.push(WatchEvent {
+                id,
+                episode: episode_id.1,
+                time,
+                runtime,
+            });
+        }
+    }
+    /// Removes the episodes the bookmark went back over from the watch log,
+    /// so that watching them again isn't counted twice
+    pub fn unlog_reverted(&mut self, before: Progress) {
+        let id = before.episode.0;
+        let Some(after) = self.get_bookmark(id).map(Bookmark::progress) else {
+            return;
+        };
+        for episode in self.passed_episodes(&after, &before) {
+            // The log may contain the episode in the other format
+            let details = self.movie_details.get(&id);
+            let same = |event: &WatchEvent| {
+                event.id == id
+                    && match details {
+                        Some(details) => {
+                            details.as_total_episodes(&event.episode)
+                                == details.as_total_episodes(&episode)
+                        }
+                        None => event.episode == episode,
+                    }
+            };
+            if let Some(index) = self.watch_log.iter().rposition(same) {
+                self.watch_log.remove(index);
+            }
+        }
+    }
+    /// The episodes watched when going from progress `from` to `to`, in the format of `from`.
+    /// Without details, only the episode of `from` is known to be watched
+    fn passed_episodes(&self, from: &Progress, to: &Progress) -> Vec<Episode> {
+        let id = from.episode.0;
+        let details = self.movie_details.get(&id).filter(|_| {
+            self.get_bookmark(id)
+                .is_some_and(|bookmark| bookmark.sync_mode == SyncMode::Tmdb)
+        });
+        match details {
+            Some(details) => {
+                // Number of watched episodes
+                let position = |progress: &Progress| {
+                    let total = details.as_total_episodes(&progress.episode.1).episode;
+                    total.saturating_sub(1) + usize::from(progress.finished)
+                };
+                (position(from)..position(to))
+                    .map(|watched| {
+                        let episode = TotalEpisode {
+                            episode: watched + 1,
+                        };
+                        details.in_format_of(details.as_seasonal_episode(&episode), &from.episode.1)
+                    })
+                    .collect()
+            }
+            None if to.episode != from.episode => vec![from.episode.1.clone()],
+            None => Vec::new(),
+        }
+    }
+    pub fn stats(&self) -> Stats {
+        let today = calendar::today();
+        let dates: Vec<NaiveDate> = self
+            .watch_log
+            .iter()
+            .map(|event| event.time.with_timezone(&Local).date_naive())
+            .collect();
+        let runtimes: Vec<Option<usize>> = self
+            .watch_log
+            .iter()
+            .map(|event| {
+                event
+                    .runtime
+                    .or_else(|| self.movie_details.get(&event.id)?.episode_runtime())
+            })
+            .collect();
+        let mut genres: HashMap<&str, usize> = HashMap::new();
+        for event in &self.watch_log {
+            let Some(bookmark) = self.get_bookmark(event.id) else {
+                continue;
+            };
+            for name in bookmark
+                .movie
+                .genre_ids
+                .iter()
+                .filter_map(|id| genre_name(*id))
+            {
+                *genres.entry(name).or_default() += 1;
+            }
+        }
+        let mut genres: Vec<(String, usize)> = genres
+            .into_iter()
+            .map(|(name, count)| (name.to_owned(), count))
+            .collect();
+        genres.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let started = self
+            .bookmarks
+            .iter()
+            .filter(|bookmark| bookmark.status() != WatchStatus::PlanToWatch)
+            .count();
+        let completed = self
+            .bookmarks
+            .iter()
+            .filter(|bookmark| bookmark.status() == WatchStatus::Completed)
+            .count();
+        let backlog: Vec<usize> = self
+            .bookmarks
+            .iter()
+            .filter(|bookmark| bookmark.status() == WatchStatus::Watching)
+            .filter_map(|bookmark| {
+                let details = self.movie_details.get(&bookmark.movie.id)?;
+                Some(bookmark.episodes_behind(details))
+            })
+            .filter(|behind| *behind > 0)
+            .collect();
+        let (longest_streak_days, current_streak_days) =
+            streaks(&dates.iter().copied().collect(), today);
+        Stats {
+            episodes_watched: self.watch_log.len(),
+            watch_time_minutes: runtimes.iter().flatten().sum(),
+            episodes_without_runtime: runtimes.iter().filter(|runtime| runtime.is_none()).count(),
+            episodes_per_week: count_periods(&dates, today, StatsRange::Weeks),
+            episodes_per_month: count_periods(&dates, today, StatsRange::Months),
+            genres,
+            completion_rate: (started > 0).then(|| completed as f32 / started as f32),
+            longest_streak_days,
+            current_streak_days,
+            backlog_episodes: backlog.iter().sum(),
+            backlog_shows: backlog.len(),
+        }
+    }
+    /// Writes the statistics as JSON file to `path`
+    pub fn export_stats(&self, path: PathBuf) -> Command<Message> {
+        match serde_json::to_string_pretty(&self.stats()) {
+            Ok(json) => Command::perform(save::write_export(path, json), Message::StatsExported),
+            Err(e) => {
+                error!("failed to serialize the statistics: {e}");
+                Command::perform(async { Err(SaveError::Format) }, Message::StatsExported)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::BookmarkMessage;
+    use crate::movie::test_movie;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+    fn counts(periods: &[PeriodCount]) -> Vec<(NaiveDate, usize)> {
+        periods
+            .iter()
+            .map(|period| (period.start, period.episodes))
+            .collect()
+    }
+
+    #[test]
+    fn counts_episodes_per_week() {
+        // 2024-03-13 is a wednesday
+        let dates = [
+            date(3, 11),
+            date(3, 17),
+            date(3, 10),
+            date(1, 1),
+            date(3, 18),
+        ];
+        let periods = count_periods(&dates, date(3, 13), StatsRange::Weeks);
+        assert_eq!(periods.len(), CHART_PERIODS);
+        let counts = counts(&periods);
+        assert_eq!(counts.last(), Some(&(date(3, 11), 2)));
+        assert_eq!(counts[CHART_PERIODS - 2], (date(3, 4), 1));
+        assert_eq!(counts[CHART_PERIODS - 11], (date(1, 1), 1));
+        // Weeks after today and before the first period are not counted
+        assert_eq!(counts.iter().map(|(_, count)| count).sum::<usize>(), 4);
+    }
+    #[test]
+    fn counts_episodes_per_month() {
+        let dates = [date(3, 31), date(3, 1), date(2, 29), date(4, 1)];
+        let periods = count_periods(&dates, date(3, 15), StatsRange::Months);
+        let counts = counts(&periods);
+        assert_eq!(
+            counts.first(),
+            Some(&(NaiveDate::from_ymd_opt(2023, 4, 1).unwrap(), 0))
+        );
+        assert_eq!(counts[CHART_PERIODS - 1], (date(3, 1), 2));
+        assert_eq!(counts[CHART_PERIODS - 2], (date(2, 1), 1));
+    }
+    #[test]
+    fn counts_no_episodes() {
+        let periods = count_periods(&[], date(3, 13), StatsRange::Weeks);
+        assert!(periods.iter().all(|period| period.episodes == 0));
+    }
+    #[test]
+    fn finds_the_longest_and_current_streak() {
+        let days: BTreeSet<NaiveDate> =
+            [date(3, 1), date(3, 2), date(3, 3), date(3, 5), date(3, 6)].into();
+        assert_eq!(streaks(&days, date(3, 6)), (3, 2));
+        // A streak which ended yesterday is still going on
+        assert_eq!(streaks(&days, date(3, 7)), (3, 2));
+        assert_eq!(streaks(&days, date(3, 8)), (3, 0));
+    }
+    #[test]
+    fn streaks_span_month_ends() {
+        let days: BTreeSet<NaiveDate> = [date(2, 28), date(2, 29), date(3, 1)].into();
+        assert_eq!(streaks(&days, date(3, 1)), (3, 3));
+        assert_eq!(streaks(&BTreeSet::new(), date(3, 1)), (0, 0));
+    }
+    #[test]
+    fn going_back_removes_the_episode_from_the_log() {
+        let movie = test_movie(1, "Show");
+        let mut bookmark = Bookmark::from(&movie);
+        bookmark.sync_mode = SyncMode::NoSync;
+        let mut state = State {
+            bookmarks: vec![bookmark],
+            ..State::default()
+        };
+        let apply = |state: &mut State, message: BookmarkMessage| {
+            let before = state.bookmarks[0].progress();
+            let advances = matches!(message, BookmarkMessage::IncrE(_));
+            let _ = state.bookmarks[0].apply(message);
+            if advances {
+                state.log_if_advanced(Some(before));
+            } else {
+                state.unlog_reverted(before);
+            }
+        };
+        apply(&mut state, BookmarkMessage::IncrE(None));
+        apply(&mut state, BookmarkMessage::IncrE(None));
+        assert_eq!(state.watch_log.len(), 2);
+        apply(&mut state, BookmarkMessage::DecrE(None));
+        assert_eq!(state.watch_log.len(), 1);
+        apply(&mut state, BookmarkMessage::IncrE(None));
+        let episodes: Vec<&Episode> = state.watch_log.iter().map(|event| &event.episode).collect();
+        assert_eq!(
+            episodes,
+            [
+                &Episode::Total(TotalEpisode { episode: 1 }),
+                &Episode::Total(TotalEpisode { episode: 2 })
+            ]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use iced::theme::{Palette, Theme};
+use iced::Color;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::config::FontSizes;
+use crate::save;
+use crate::state::State;
+
+/// Theme selected in the settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeChoice {
+    Light,
+    Dark,
+    /// Follows the dark mode setting of the system
+    #[default]
+    System,
+    /// Palette loaded from the themes directory by its name
+    Custom(String),
+}
+impl Display for ThemeChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeChoice::Light => write!(f, "Light"),
+            ThemeChoice::Dark => write!(f, "Dark"),
+            ThemeChoice::System => write!(f, "System"),
+            ThemeChoice::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
+/// User defined palette
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomTheme {
+    pub name: String,
+    pub palette: Palette,
+}
+/// Colors of a palette file as hex strings like `#1e1e2e`
+#[derive(Deserialize)]
+struct PaletteFile {
+    background: String,
+    text: String,
+    primary: String,
+    success: String,
+    danger: String,
+}
+impl TryFrom<PaletteFile> for Palette {
+    type Error = String;
+
+    fn try_from(file: PaletteFile) -> Result<Self, Self::Error> {
+        Ok(Palette {
+            background: parse_hex(&file.background)?,
+            text: parse_hex(&file.text)?,
+            primary: parse_hex(&file.primary)?,
+            success: parse_hex(&file.success)?,
+            danger: parse_hex(&file.danger)?,
+        })
+    }
+}
+/// Parses colors in the form `#rrggbb`
+fn parse_hex(hex: &str) -> Result<Color, String> {
+    let digits = hex.trim().trim_start_matches('#');
+    let value = u32::from_str_radix(digits, 16)
+        .ok()
+        .filter(|_| digits.len() == 6)
+        .ok_or_else(|| format!("{hex} is not a color like #1e1e2e"))?;
+    Ok(Color::from_rgb8(
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    ))
+}
+/// Directory containing the palette files
+pub fn themes_path() -> PathBuf {
+    let mut path = save::data_path();
+    path.push("themes");
+    path
+}
+/// Loads every `*.toml` palette in the themes directory. Invalid files are skipped
+pub async fn load_custom_themes() -> Vec<CustomTheme> {
+    let dir = themes_path();
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        info!("no themes directory at {dir:?}");
+        return Vec::new();
+    };
+    let mut themes: Vec<CustomTheme> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let palette = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    toml::from_str::<PaletteFile>(&contents).map_err(|e| e.to_string())
+                })
+                .and_then(Palette::try_from);
+            match palette {
+                Ok(palette) => Some(CustomTheme { name, palette }),
+                Err(e) => {
+                    error!("invalid theme {path:?}: {e}");
+                    None
+                }
+            }
+        })
+        .collect();
+    themes.sort_by(|a, b| a.name.cmp(&b.name));
+    themes
+}
+/// True if the system prefers a dark theme
+pub async fn detect_system_dark() -> bool {
+    dark_light::detect() == dark_light::Mode::Dark
+}
+/// Sizes and colors used by the views, derived from the config and the active theme
+#[derive(Debug, Clone, Copy)]
+pub struct ViewStyle {
+    pub fonts: FontSizes,
+    /// Secondary text
+    pub muted: Color,
+    pub error: Color,
+    /// Matches of the search
+    pub highlight: Color,
+}
+impl ViewStyle {
+    pub fn new(theme: &Theme, fonts: FontSizes) -> Self {
+        let palette = theme.palette();
+        Self {
+            fonts,
+            muted: Color {
+                a: 0.6,
+                ..palette.text
+            },
+            error: palette.danger,
+            highlight: palette.primary,
+        }
+    }
+}
+impl State {
+    /// Resolves the theme choice of the config. Has to be called whenever one of its inputs changes
+    pub fn apply_theme(&mut self) {
+        self.theme = match &self.config.theme {
+            ThemeChoice::Light => Theme::Light,
+            ThemeChoice::Dark => Theme::Dark,
+            ThemeChoice::System if self.system_dark => Theme::Dark,
+            ThemeChoice::System => Theme::Light,
+            ThemeChoice::Custom(name) => {
+                match self.custom_themes.iter().find(|theme| &theme.name == name) {
+                    Some(custom) => Theme::custom(custom.name.clone(), custom.palette),
+                    None => {
+                        warn!("theme {name} not found, using the dark theme");
+                        Theme::Dark
+                    }
+                }
+            }
+        };
+    }
+    pub fn view_style(&self) -> ViewStyle {
+        ViewStyle::new(&self.theme, self.config.fonts)
+    }
+    /// All themes which can be selected
+    pub fn theme_choices(&self) -> Vec<ThemeChoice> {
+        [ThemeChoice::System, ThemeChoice::Light, ThemeChoice::Dark]
+            .into_iter()
+            .chain(
+                self.custom_themes
+                    .iter()
+                    .map(|theme| ThemeChoice::Custom(theme.name.clone())),
+            )
+            .collect()
+    }
+}
@@ -5,6 +5,8 @@ use crate::{
 };
 use anyhow::Result;
 use reqwest::blocking::Client;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
 use serde::Deserialize;
 use tracing::info;
 
@@ -16,11 +18,12 @@ pub enum RequestType {
     TvDetails { id: MovieId },
     Poster { id: MovieId, path: String },
     EpisodeDetails { id: EpisodeId },
+    SeasonDetails { id: MovieId, season: usize },
 }
 impl RequestType {
-    pub fn url(&self) -> String {
+    pub fn url(&self, language: &str) -> String {
         let base_url = "https://api.themoviedb.org/3/";
-        let rest = "language=en-US&page=1";
+        let rest = format!("language={language}&page=1");
         let body = match self {
             RequestType::TvSearch { query } => {
                 let query_cleaned = query.replace(' ', "%20");
@@ -30,6 +33,9 @@ impl RequestType {
             RequestType::Poster { id: _, path } => {
                 return format!("https://image.tmdb.org/t/p/w500/{path}")
             }
+            RequestType::SeasonDetails { id, season } => {
+                format!("tv/{movie_id}/season/{season}?", movie_id = id.id())
+            }
             RequestType::EpisodeDetails { id } => {
                 let seasonal = match &id.1 {
                     crate::movie_details::Episode::Seasonal(e) => e.clone(),
@@ -49,8 +55,8 @@ impl RequestType {
         format!("{base_url}{body}{rest}")
     }
 }
-pub async fn send_request(request: RequestType) -> Result<String> {
-    let url = request.url();
+pub async fn send_request(request: RequestType, language: String) -> Result<String> {
+    let url = request.url(&language);
     info!("send request with {}", &url[8..]);
     let request = Client::new()
         .get(url)
@@ -62,8 +68,47 @@ pub async fn send_request(request: RequestType) -> Result<String> {
     let data: String = response.text().unwrap();
     Ok(data)
 }
+/// Response of a conditional request
+#[derive(Debug, Clone)]
+pub enum CachedResponse {
+    /// The resource changed since the given entity tag
+    Modified {
+        text: String,
+        etag: Option<String>,
+    },
+    NotModified,
+}
+/// Sends the request with the `If-None-Match` header set to `etag`,
+/// so that unchanged resources don't have to be transferred again
+pub async fn send_cached_request(
+    request: RequestType,
+    etag: Option<String>,
+    language: String,
+) -> Result<CachedResponse> {
+    let url = request.url(&language);
+    info!("send conditional request with {}", &url[8..]);
+    let mut request = Client::new()
+        .get(url)
+        .header("accept", "application/json")
+        .bearer_auth(BEAR);
+    if let Some(etag) = etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    let response = request.send()?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(CachedResponse::NotModified);
+    }
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_owned);
+    let text = response.error_for_status()?.text()?;
+    Ok(CachedResponse::Modified { text, etag })
+}
+/// Downloads raw bytes, like posters, which the image host serves without a language
 pub async fn send_byte_request(request: RequestType) -> Result<Vec<u8>> {
-    let url = request.url();
+    let url = request.url("");
     info!("send request with {}", &url[8..]);
     let request = Client::new()
         .get(url)
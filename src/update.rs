@@ -1,15 +1,25 @@
-use iced::{widget, window, Command};
+use std::{path::PathBuf, time::SystemTime};
+
+use chrono::Local;
+use iced::{window, Command, Size};
 use tracing::{debug, error, info, warn};
 
 use crate::{
+    archive::ArchiveConfirmation,
     bookmark::{Bookmark, Poster},
+    collection::parse_tag,
+    config::{AdvanceMode, Setting},
     filter::Filter,
-    id::{EpisodeId, MovieIndex},
-    link::Link,
-    message::{BookmarkMessage, LinkMessage, Message, ShiftPressed},
-    save::load_poster,
+    id::{EpisodeId, MovieId, MovieIndex},
+    keymap, launch,
+    link::{Link, LinkAction, LinkActionKind, NamedLink},
+    message::{BookmarkMessage, LinkMessage, Message},
+    player,
+    save::{self, load_poster},
+    selection::BulkAction,
     state::{InputKind, State},
-    tmdb::{self, RequestType},
+    theme,
+    tmdb::{self, CachedResponse, RequestType},
 };
 pub struct StateUpdate {
     command: Command<Message>,
@@ -72,9 +82,7 @@ impl State {
                         self.gui.input_caches[input] = String::new();
                     }
                     Filter::Details(_) => warn!("Input submit in details view received"),
-                    Filter::Bookmarks | Filter::Completed => {
-                        info!("ignore input submit in current filter")
-                    }
+                    _ => info!("ignore input submit in current filter"),
                 },
                 InputKind::EpisodeInput => {
                     let Filter::Details(movie_id) = self.gui.filter else {
@@ -104,17 +112,89 @@ impl State {
                         ))
                         .into();
                 }
-                InputKind::LinkInput => {
+                InputKind::CalendarPath => {
+                    let path = self.gui.input_caches[input].trim();
+                    self.config.calendar_path = if path.is_empty() {
+                        None
+                    } else {
+                        Some(PathBuf::from(path))
+                    };
+                    info!(
+                        "calendar subscription path set to {:?}",
+                        self.config.calendar_path
+                    );
+                    update = StateUpdate::new(self.write_calendar_subscription()).into();
+                }
+                InputKind::LocalDirInput => {
+                    let Filter::Details(movie_id) = self.gui.filter else {
+                        return StateUpdate::default();
+                    };
+                    let Some(bookmark) = self.bookmarks.with_id_mut(movie_id) else {
+                        return StateUpdate::default();
+                    };
+                    let dir = self.gui.input_caches[InputKind::LocalDirInput].trim();
+                    bookmark.local_dir = (!dir.is_empty()).then(|| PathBuf::from(dir));
+                    self.libraries.remove(&movie_id);
+                    update = StateUpdate::new(self.scan_library(movie_id)).into();
+                }
+                InputKind::TagInput => {
                     let Filter::Details(movie_id) = self.gui.filter else {
                         return StateUpdate::default();
                     };
-                    let input = &self.gui.input_caches[input];
+                    let Some(tag) = parse_tag(&self.gui.input_caches[input]) else {
+                        return StateUpdate::default();
+                    };
+                    self.gui.input_caches[input] = String::new();
+                    update = self
+                        .update_state(Message::BookmarkMessage(
+                            movie_id,
+                            BookmarkMessage::AddTag(tag),
+                        ))
+                        .into();
+                }
+                InputKind::CollectionNameInput => {
+                    update = self.update_state(Message::SaveCollection).into();
+                }
+                InputKind::BulkTagInput => {
+                    let Some(tag) = parse_tag(&self.gui.input_caches[input]) else {
+                        return StateUpdate::default().just_saved();
+                    };
+                    update = self
+                        .update_state(Message::Bulk(BulkAction::AddTag(tag)))
+                        .into();
+                }
+                InputKind::LinkInput | InputKind::LinkNameInput | InputKind::LinkCommandInput => {
+                    let Filter::Details(movie_id) = self.gui.filter else {
+                        return StateUpdate::default();
+                    };
+                    let input = &self.gui.input_caches[InputKind::LinkInput];
                     let link = Link::new(input);
                     let Ok(link) = link else {
                         error!("{input} is not a valid link. Error {link:?}");
                         return StateUpdate::default();
                     };
-                    self.links.insert(movie_id, link);
+                    let links = self.links.entry(movie_id).or_default();
+                    let name = self.gui.input_caches[InputKind::LinkNameInput].trim();
+                    let name = if name.is_empty() {
+                        links.unused_name()
+                    } else {
+                        name.to_owned()
+                    };
+                    let action = match self.gui.link_action {
+                        LinkActionKind::Copy => LinkAction::Copy,
+                        LinkActionKind::Open => LinkAction::Open,
+                        LinkActionKind::Command => {
+                            let command = &self.gui.input_caches[InputKind::LinkCommandInput];
+                            let argv = launch::split_arguments(command);
+                            if argv.is_empty() {
+                                error!("the command of the link is empty");
+                                return StateUpdate::default();
+                            }
+                            LinkAction::Command(argv)
+                        }
+                    };
+                    links.set(self.gui.editing_link, NamedLink { name, link, action });
+                    self.set_link_input_caches(movie_id, None);
                 }
             },
 
@@ -134,7 +214,8 @@ impl State {
                             send_request = reformated_request;
                         }
                     };
-                    Command::perform(tmdb::send_request(send_request), |data| {
+                    let language = self.config.language.clone();
+                    Command::perform(tmdb::send_request(send_request, language), |data| {
                         Message::RequestResponse(data.ok(), request)
                     })
                 };
@@ -149,6 +230,7 @@ impl State {
                     RequestType::TvSearch { .. } => self.response_tv_search(text),
                     RequestType::TvDetails { .. } => self.response_tv_details(text),
                     RequestType::EpisodeDetails { id } => self.response_episode_details(text, id),
+                    RequestType::SeasonDetails { id, .. } => self.response_season_details(text, id),
                     // is handled by the `Message::RequestPoster` case
                     RequestType::Poster { .. } => Command::none(),
                 };
@@ -161,12 +243,73 @@ impl State {
                 };
                 self.movie_posters.insert(id, Poster::Image(handle));
             }
+            Message::RefreshTick => {
+                let now = SystemTime::now();
+                let Some(id) = self.next_refresh(now) else {
+                    return StateUpdate::default().just_saved();
+                };
+                self.refresh_attempted.insert(id, now);
+                self.refreshing.insert(id);
+                update = StateUpdate::new(self.refresh_details(id))
+                    .just_saved()
+                    .into();
+            }
+            Message::Refreshed(id, response) => {
+                self.refreshing.remove(&id);
+                match response {
+                    Some(CachedResponse::Modified { text, etag }) => {
+                        if let Some(etag) = etag {
+                            self.details_etags.insert(id, etag);
+                        }
+                        update = StateUpdate::new(self.response_tv_details(text)).into();
+                    }
+                    Some(CachedResponse::NotModified) => {
+                        debug!("details of {id} did not change");
+                        self.details_refreshed.insert(id, SystemTime::now());
+                        update = StateUpdate::default().just_saved().into();
+                    }
+                    None => {
+                        warn!("unsuccessfull refresh of {id}");
+                        update = StateUpdate::default().just_saved().into();
+                    }
+                }
+            }
             Message::FilterChanged(new_filter) => {
                 debug!(
                     "changed filter from {:?} gui.to {:?}",
                     self.gui.filter, new_filter
                 );
+                if let (Filter::Details(_), false) =
+                    (new_filter, matches!(self.gui.filter, Filter::Details(_)))
+                {
+                    self.gui.previous_filter = self.gui.filter;
+                }
+                // The selection belongs to the shown list, looking at the details keeps it
+                let details = |filter| matches!(filter, Filter::Details(_));
+                if new_filter != self.gui.filter
+                    && !details(new_filter)
+                    && !details(self.gui.filter)
+                {
+                    self.gui.selection.clear();
+                    self.gui.selection_anchor = None;
+                    self.gui.pending_bulk = None;
+                }
                 self.gui.filter = new_filter;
+                if Filter::Settings == new_filter {
+                    self.gui.setting_inputs = Setting::ALL
+                        .iter()
+                        .map(|setting| (*setting, setting.get(&self.config)))
+                        .collect();
+                    self.gui.setting_errors.clear();
+                }
+                if Filter::Calendar == new_filter {
+                    self.gui.input_caches[InputKind::CalendarPath] = self
+                        .config
+                        .calendar_path
+                        .as_ref()
+                        .map(|path| path.display().to_string())
+                        .unwrap_or_default();
+                }
                 // Load the current episode details if not already loaded
                 if let Filter::Details(movie_id) = new_filter {
                     self.set_detail_input_caches(movie_id);
@@ -184,12 +327,245 @@ impl State {
                     }
                 }
             }
+            Message::CalendarRangeChanged(range) => self.gui.calendar_range = range,
+            Message::Notified(result) => {
+                if let Err(e) = result {
+                    warn!("failed to show notification: {e}");
+                }
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::ClearNotifications => self.notifications.clear(),
+            Message::QuietHoursChanged(quiet_hours) => {
+                self.config.quiet_hours = quiet_hours;
+                update = StateUpdate::new(self.deliver_held_back(Local::now())).into();
+            }
+            Message::DeliverHeldBack => {
+                let now = Local::now();
+                if self.is_quiet(now) {
+                    return StateUpdate::default().just_saved();
+                }
+                update = StateUpdate::new(self.deliver_held_back(now)).into();
+            }
+            Message::ExportCalendar => {
+                let path = self
+                    .config
+                    .calendar_path
+                    .clone()
+                    .unwrap_or_else(save::calendar_path);
+                update = StateUpdate::new(self.export_calendar(path)).into();
+            }
+            Message::StatsRangeChanged(range) => self.gui.stats_range = range,
+            Message::ExportStats => {
+                update = StateUpdate::new(self.export_stats(save::stats_path())).into();
+            }
+            Message::StatsExported(result) => {
+                self.gui.stats_export = Some(match result {
+                    Ok(path) => format!("Exported statistics to {}", path.display()),
+                    Err(e) => format!("Statistics export failed: {e:?}"),
+                });
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::CalendarSubscriptionWritten(result) => {
+                if let Err(e) = result {
+                    warn!("failed to write the calendar subscription: {e:?}");
+                }
+                self.gui.calendar_writing = false;
+                let cmd = if self.gui.calendar_dirty {
+                    self.write_calendar_subscription()
+                } else {
+                    Command::none()
+                };
+                update = StateUpdate::new(cmd).just_saved().into();
+            }
+            Message::CalendarExported(result) => {
+                self.gui.calendar_export = Some(match result {
+                    Ok(path) => format!("Exported calendar to {}", path.display()),
+                    Err(e) => format!("Calendar export failed: {e:?}"),
+                });
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::ScanLibrary(id) => {
+                update = StateUpdate::new(self.scan_library(id)).just_saved().into();
+            }
+            Message::LibraryScanned(id, library) => {
+                self.libraries.insert(id, library);
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::PlaybackEnded(id, episode, progress) => {
+                let progress = match progress {
+                    Ok(progress) => progress,
+                    Err(e) => {
+                        warn!("couldn't follow the playback: {e}");
+                        return StateUpdate::default().just_saved();
+                    }
+                };
+                let threshold = self.config.watched_threshold * 100.;
+                let details = self.movie_details.get(&id).cloned();
+                let Some(bookmark) = self.bookmarks.with_id_mut(id) else {
+                    return StateUpdate::default().just_saved();
+                };
+                if progress < threshold {
+                    info!("stopped at {progress:.0}%, keep the progress of {id}");
+                    return StateUpdate::default().just_saved();
+                }
+                // The progress might have been changed manually while the player was open
+                if *bookmark.active_episode() != episode {
+                    info!("progress of {id} changed during playback, not advancing");
+                    return StateUpdate::default().just_saved();
+                }
+                let watched = bookmark.progress();
+                update = StateUpdate::new(bookmark.apply(BookmarkMessage::IncrE(details))).into();
+                self.log_if_advanced(Some(watched));
+            }
+            Message::AskAdvance(id, episode) => {
+                // A newer question about the same bookmark replaces the pending one
+                match self.gui.advance_prompts.iter_mut().find(|(i, _)| *i == id) {
+                    Some(prompt) => prompt.1 = episode,
+                    None => self.gui.advance_prompts.push((id, episode)),
+                }
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::AnswerAdvance(id, watched) => {
+                let Some(index) = self.gui.advance_prompts.iter().position(|(i, _)| *i == id)
+                else {
+                    return StateUpdate::default().just_saved();
+                };
+                let (_, episode) = self.gui.advance_prompts.remove(index);
+                let details = self.movie_details.get(&id).cloned();
+                match self.bookmarks.with_id_mut(id) {
+                    Some(bookmark) if watched && *bookmark.active_episode() == episode => {
+                        let watched = bookmark.progress();
+                        let cmd = bookmark.apply(BookmarkMessage::IncrE(details));
+                        update = StateUpdate::new(cmd).into();
+                        self.log_if_advanced(Some(watched));
+                    }
+                    _ => update = StateUpdate::default().just_saved().into(),
+                }
+            }
+            Message::AdvanceModeChanged(mode) => self.config.advance_on_play = mode,
+            Message::TrackPlayerProgressToggled(track) => self.config.track_player_progress = track,
+            Message::WindowResized(width) => {
+                self.gui.window_width = width;
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::CardWidthChanged(width) => self.config.card_width = width,
+            Message::BookmarkLayoutChanged(layout) => self.config.bookmark_layout = layout,
+            Message::SortChanged(sort) => self.config.sort = sort,
+            Message::DragStart(id) => {
+                self.gui.dragging = Some(id);
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::DragOver(target) => {
+                if let Some(dragged) = self.gui.dragging.filter(|dragged| *dragged != target) {
+                    self.move_bookmark(dragged, target);
+                }
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::DragEnd => self.gui.dragging = None,
+            Message::TagFilterToggled(tag) => {
+                if !self.gui.tag_filter.remove(&tag) {
+                    self.gui.tag_filter.insert(tag);
+                }
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::SaveCollection => {
+                let name = self.gui.input_caches[InputKind::CollectionNameInput].clone();
+                let Some(index) = self.save_collection(&name) else {
+                    warn!("a collection needs a name and at least one selected tag");
+                    return StateUpdate::default().just_saved();
+                };
+                info!("Saved collection {name}");
+                self.gui.input_caches[InputKind::CollectionNameInput] = String::new();
+                let cmd = self.update_state(Message::FilterChanged(Filter::Collection(index)));
+                update = cmd.into();
+            }
+            Message::RemoveCollection(index) => {
+                if index < self.collections.len() {
+                    let collection = self.collections.remove(index);
+                    info!("Removed collection {}", collection.name);
+                }
+                match self.gui.filter {
+                    Filter::Collection(shown) if shown == index => {
+                        let cmd = self.update_state(Message::FilterChanged(Filter::Bookmarks));
+                        update = cmd.into();
+                    }
+                    // The collections after the removed one move up
+                    Filter::Collection(shown) if shown > index => {
+                        self.gui.filter = Filter::Collection(shown - 1);
+                    }
+                    _ => {}
+                }
+            }
+            Message::NotesEdited(action) => {
+                let Filter::Details(movie_id) = self.gui.filter else {
+                    return StateUpdate::default().just_saved();
+                };
+                let edit = action.is_edit();
+                self.gui.notes_editor.perform(action);
+                if !edit {
+                    return StateUpdate::default().just_saved();
+                }
+                if let Some(bookmark) = self.bookmarks.with_id_mut(movie_id) {
+                    bookmark.notes = self.gui.notes_editor.text().trim_end().to_owned();
+                }
+            }
+            Message::EpisodeNoteChanged(id, note) => {
+                if note.trim().is_empty() {
+                    self.episode_notes.remove(&id);
+                } else {
+                    self.episode_notes.insert(id, note);
+                }
+            }
+            Message::ThemeChanged(choice) => {
+                self.config.theme = choice;
+                self.apply_theme();
+            }
+            Message::ReloadThemes => {
+                let cmd = Command::perform(theme::load_custom_themes(), Message::ThemesLoaded);
+                update = StateUpdate::new(cmd).just_saved().into();
+            }
+            Message::ThemesLoaded(themes) => {
+                self.custom_themes = themes;
+                self.apply_theme();
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::DetectSystemTheme => {
+                let cmd =
+                    Command::perform(theme::detect_system_dark(), Message::SystemThemeDetected);
+                update = StateUpdate::new(cmd).just_saved().into();
+            }
+            Message::SystemThemeDetected(dark) => {
+                if self.system_dark != dark {
+                    self.system_dark = dark;
+                    self.apply_theme();
+                }
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::SettingChanged(setting, input) => {
+                match setting.set(&mut self.config, &input) {
+                    Ok(()) => {
+                        self.gui.setting_errors.remove(&setting);
+                    }
+                    Err(e) => {
+                        self.gui.setting_errors.insert(setting, e);
+                    }
+                }
+                self.gui.setting_inputs.insert(setting, input);
+                if matches!(setting, Setting::WindowWidth | Setting::WindowHeight) {
+                    let size = Size::new(self.config.window_width, self.config.window_height);
+                    update = StateUpdate::new(window::resize(window::Id::MAIN, size)).into();
+                }
+            }
             Message::AddBookmark(id) => {
                 // We don't want to add the movie if we already have a bookmark for that movie
                 if self.get_bookmark(id).is_some() {
                     info!("Ignore add bookmark message since a bookmark with that movie id already exists");
                     return StateUpdate::default();
                 }
+                // Bookmarks deleted before continue where they were
+                if let Some(cmd) = self.restore_bookmark(id) {
+                    return StateUpdate::new(cmd);
+                }
                 let Some(movie) = self.movies.with_id(id) else {
                     warn!("Tried to add a bookmark for a movie, which is currently not loaded");
                     return StateUpdate::default();
@@ -197,22 +573,80 @@ impl State {
                 self.bookmarks.push(Bookmark::from(movie));
             }
             Message::RemoveBookmark(id) => {
-                let Some(index) = self.bookmarks.iter().position(|b| b.movie.id == id) else {
-                    warn!(
-                        "Tried to remove bookmark with {}, but no such bookmark exists",
-                        id,
-                    );
-                    return StateUpdate::default();
+                self.gui.archive_confirmation = Some(ArchiveConfirmation::Archive(id));
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::PurgeBookmark(id) => {
+                self.gui.archive_confirmation = Some(ArchiveConfirmation::Purge(id));
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::PurgeArchive => {
+                self.gui.archive_confirmation = Some(ArchiveConfirmation::PurgeAll);
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::ConfirmArchive(confirmed) => {
+                let Some(confirmation) = self.gui.archive_confirmation.take() else {
+                    return StateUpdate::default().just_saved();
                 };
-                debug!("Remove gui.bookmark {:?}", &self.bookmarks[index]);
-                self.bookmarks.remove(index);
-                if Filter::Details(id) == self.gui.filter {
-                    let cmd = self.update_state(Message::FilterChanged(Filter::Bookmarks));
-                    update = cmd.into();
+                if !confirmed {
+                    return StateUpdate::default().just_saved();
+                }
+                let purged = match confirmation {
+                    ArchiveConfirmation::Archive(id) => {
+                        self.archive_bookmark(id);
+                        if Filter::Details(id) == self.gui.filter {
+                            let filter = self.gui.previous_filter;
+                            return self.update_state(Message::FilterChanged(filter));
+                        }
+                        return StateUpdate::default();
+                    }
+                    ArchiveConfirmation::Purge(id) => vec![id],
+                    ArchiveConfirmation::PurgeAll => self
+                        .archive
+                        .iter()
+                        .map(|archived| archived.bookmark.movie.id)
+                        .collect(),
+                };
+                let bookmarks = if purged.len() == 1 {
+                    "bookmark"
+                } else {
+                    "bookmarks"
+                };
+                self.gui.purge_info = Some(format!("Deleted {} {bookmarks}", purged.len()));
+                update = StateUpdate::new(self.purge(&purged)).into();
+            }
+            Message::RestoreBookmark(id) => {
+                if let Some(cmd) = self.restore_bookmark(id) {
+                    update = StateUpdate::new(cmd).into();
                 }
             }
+            Message::PostersRemoved(count) => {
+                if count > 0 {
+                    info!("removed {count} unused posters");
+                    if let Some(purge_info) = &mut self.gui.purge_info {
+                        purge_info.push_str(&format!(" and {count} unused posters"));
+                    }
+                }
+                update = StateUpdate::default().just_saved().into();
+            }
             Message::BookmarkMessage(id, message) => {
                 if let Some(bookmark) = self.bookmarks.with_id_mut(id) {
+                    let watched = matches!(
+                        message,
+                        BookmarkMessage::IncrE(_)
+                            | BookmarkMessage::FinishSeason(_)
+                            | BookmarkMessage::JumpToLatest(_)
+                    )
+                    .then(|| bookmark.progress());
+                    let reverted =
+                        matches!(message, BookmarkMessage::DecrE(_)).then(|| bookmark.progress());
+                    let moves_progress = matches!(
+                        message,
+                        BookmarkMessage::IncrE(_)
+                            | BookmarkMessage::DecrE(_)
+                            | BookmarkMessage::FinishSeason(_)
+                            | BookmarkMessage::JumpToLatest(_)
+                    );
                     let cmd = bookmark.apply(message);
                     let current_episode = bookmark.current_episode_id();
                     let new_update = if !self.episode_details.contains_key(&current_episode) {
@@ -225,11 +659,85 @@ impl State {
                         StateUpdate::new(cmd)
                     };
                     update = new_update.into();
+                    self.log_if_advanced(watched);
+                    if let Some(before) = reverted {
+                        self.unlog_reverted(before);
+                    }
+                    if moves_progress && Filter::Details(id) == self.gui.filter {
+                        self.set_progress_input_caches(id);
+                    }
                 } else {
                     warn!("bookmark message received, that couldn't be applied. Mes: {message:?} movie_id: {id} Bookmarks: {bookmarks:?}",message=message, id=id,bookmarks=&self.bookmarks);
                 }
             }
+            Message::BulkStatus(status) => {
+                let ids: Vec<MovieId> = self
+                    .filtered_bookmarks()
+                    .iter()
+                    .map(|bookmark| bookmark.movie.id)
+                    .collect();
+                // Goes through the bulk actions so that it can be undone
+                update =
+                    StateUpdate::new(self.apply_bulk_to(ids, BulkAction::SetStatus(status))).into();
+            }
+            Message::ToggleSelecting => {
+                self.gui.selecting = !self.gui.selecting;
+                self.gui.selection.clear();
+                self.gui.selection_anchor = None;
+                self.gui.pending_bulk = None;
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::ToggleSelected(id) => {
+                self.toggle_selected(id);
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::SelectAllShown => {
+                self.select_all_shown();
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::ClearSelection => {
+                self.gui.selection.clear();
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::Bulk(action) => {
+                if action.needs_confirmation() {
+                    self.gui.pending_bulk = Some(action);
+                    return StateUpdate::default().just_saved();
+                }
+                update = StateUpdate::new(self.apply_bulk(action)).into();
+            }
+            Message::ConfirmBulk(confirmed) => {
+                let Some(action) = self.gui.pending_bulk.take() else {
+                    return StateUpdate::default().just_saved();
+                };
+                if !confirmed {
+                    return StateUpdate::default().just_saved();
+                }
+                update = StateUpdate::new(self.apply_bulk(action)).into();
+            }
+            Message::Undo => self.undo(),
+            Message::DismissUndo => {
+                self.gui.undo = None;
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::BookmarksExported(result) => {
+                self.gui.bookmarks_export = Some(match result {
+                    Ok(path) => format!("Exported bookmarks to {}", path.display()),
+                    Err(e) => format!("Bookmark export failed: {e:?}"),
+                });
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::DismissNewEpisodes => self.new_since_launch.clear(),
             Message::LinkMessage(id, message) => {
+                // Episodes available on disk are preferred over the links
+                let mode = self.config.advance_on_play;
+                let watched = self.get_bookmark(id).map(Bookmark::progress);
+                if let LinkMessage::ActivateDefault(details) = &message {
+                    if let Some(cmd) = self.play_local(id, details.clone(), mode) {
+                        self.log_if_advanced(watched);
+                        return StateUpdate::new(cmd);
+                    }
+                }
                 let Some(bookmark) = self.bookmarks.with_id_mut(id) else {
                     warn!(
                         "couldn't find bookmark which corresponds to link at position {}",
@@ -238,27 +746,97 @@ impl State {
                     return StateUpdate::default();
                 };
                 let cmd = match message {
-                    LinkMessage::LinkToClipboard(details, shift) => {
-                        let Some(link) = self.links.with_id_mut(id) else {
+                    LinkMessage::ActivateDefault(details) => {
+                        let Some(link) = self
+                            .links
+                            .with_id(id)
+                            .and_then(|links| links.default_link())
+                        else {
                             info!("couldn't find link at position {}", id);
-                            return StateUpdate::new(
-                                bookmark.apply(BookmarkMessage::IncrE(details)),
-                            );
+                            return StateUpdate::new(player::advance(bookmark, details, mode));
                         };
-                        link.to_clipboard(bookmark, details, shift)
+                        let action = LinkAction::from_modifiers(self.gui.modifiers)
+                            .unwrap_or_else(|| link.action.clone());
+                        link.activate(&action, bookmark, details, mode, &self.config)
+                    }
+                    LinkMessage::CopyDefault(details) => {
+                        let Some(link) = self
+                            .links
+                            .with_id(id)
+                            .and_then(|links| links.default_link())
+                        else {
+                            info!("bookmark {id} has no link to copy");
+                            return StateUpdate::default().just_saved();
+                        };
+                        link.activate(
+                            &LinkAction::Copy,
+                            bookmark,
+                            details,
+                            AdvanceMode::Never,
+                            &self.config,
+                        )
+                    }
+                    LinkMessage::Activate(index, details) => {
+                        let Some(links) = self.links.with_id(id) else {
+                            warn!("bookmark {id} has no links");
+                            return StateUpdate::default();
+                        };
+                        let Some(link) = links.links.get(index) else {
+                            warn!("couldn't find link {index} of bookmark {id}");
+                            return StateUpdate::default();
+                        };
+                        // Other links like a wiki or subtitles don't mean the episode was watched
+                        let mode = if index == links.default_index() {
+                            mode
+                        } else {
+                            AdvanceMode::Never
+                        };
+                        let action = LinkAction::from_modifiers(self.gui.modifiers)
+                            .unwrap_or_else(|| link.action.clone());
+                        link.activate(&action, bookmark, details, mode, &self.config)
+                    }
+                    LinkMessage::ActionKindChanged(kind) => {
+                        self.gui.link_action = kind;
+                        Command::none()
+                    }
+                    LinkMessage::SetDefault(index) => {
+                        if let Some(links) = self.links.with_id_mut(id) {
+                            links.default = index;
+                        }
+                        Command::none()
+                    }
+                    LinkMessage::Edit(index) => {
+                        self.set_link_input_caches(id, Some(index));
+                        Command::none()
+                    }
+                    LinkMessage::Remove(index) => {
+                        if let Some(links) = self.links.with_id_mut(id) {
+                            links.remove(index);
+                            if links.is_empty() {
+                                self.links.remove(&id);
+                            }
+                        }
+                        self.set_link_input_caches(id, None);
+                        Command::none()
                     }
                 };
+                self.log_if_advanced(watched);
                 update = StateUpdate::new(cmd).into();
             }
-            Message::TabPressed => {
-                let cmd = if self.gui.shift_pressed == ShiftPressed::True {
-                    widget::focus_previous()
-                } else {
-                    widget::focus_next()
-                };
-                update = StateUpdate::new(cmd).into();
+            Message::KeyPressed(key, modifiers) => update = self.key_pressed(key, modifiers).into(),
+            Message::ToggleKeymap => {
+                self.gui.show_keymap = !self.gui.show_keymap;
+                update = StateUpdate::default().just_saved().into();
+            }
+            Message::ReloadKeymap => {
+                let cmd = Command::perform(keymap::load_keymap(), Message::KeymapLoaded);
+                update = StateUpdate::new(cmd).just_saved().into();
+            }
+            Message::KeymapLoaded(keymap) => {
+                self.keymap_loaded(keymap);
+                update = StateUpdate::default().just_saved().into();
             }
-            Message::ShiftPressed(shift) => self.gui.shift_pressed = shift,
+            Message::ModifiersChanged(modifiers) => self.gui.modifiers = modifiers,
             Message::ToggleFullscreen(mode) => {
                 let cmd = window::change_mode(window::Id::MAIN, mode);
                 update = StateUpdate::new(cmd).into();
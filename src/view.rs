@@ -2,40 +2,121 @@ use iced::alignment::Horizontal;
 use iced::theme::{self};
 use iced::widget::text::LineHeight;
 use iced::widget::{
-    button, column, container, image, row, text, text_input, Column, Image, Row, Space,
+    button, canvas, checkbox, column, container, image, mouse_area, pick_list, progress_bar, row,
+    text, text_editor, text_input, tooltip, Column, Image, Row, Space,
 };
 use iced::Length;
 use iced::{Alignment, Element};
 
-use crate::bookmark::{Bookmark, Poster, SyncMode};
+use std::time::SystemTime;
+
+use chrono::Local;
+
+use crate::bookmark::{Bookmark, Poster, SyncMode, WatchStatus};
+use crate::calendar::{self, CalendarRange};
+use crate::chart::BarChart;
+use crate::config::{AdvanceMode, QuietHours, Setting};
 use crate::filter::Filter;
-use crate::gui::{FONT_SIZE, FONT_SIZE_HEADER};
 use crate::icons::{Icon, ICON_SIZE};
-use crate::id::MovieId;
-use crate::message::{BookmarkMessage, LinkMessage, Message, ShiftPressed};
+use crate::id::{EpisodeId, MovieId};
+use crate::keymap::keymap_path;
+use crate::library::LocalLibrary;
+use crate::link::{BookmarkLinks, Link, LinkAction, LinkActionKind, LinkValues};
+use crate::message::{empty_message, BookmarkMessage, LinkMessage, Message};
 use crate::movie::TmdbMovie;
 use crate::movie_details::{Episode, EpisodeDetails, MovieDetails};
-use crate::state::{InputCaches, InputKind};
+use crate::save;
+use crate::state::{InputCaches, InputKind, State};
+use crate::stats::StatsRange;
+use crate::theme::{themes_path, ViewStyle};
 
 impl Bookmark {
     pub fn card_view<'a>(
         &'a self,
         details: Option<&MovieDetails>,
         poster: Option<&'a Poster>,
-    ) -> Element<Message> {
-        let picture_row = row![
-            Space::with_width(Length::Fill),
-            picture_button(self.movie.id, poster, Length::FillPortion(3)),
+        refreshed: Option<SystemTime>,
+        draggable: bool,
+        highlights: &[usize],
+        style: ViewStyle,
+    ) -> Element<'a, Message> {
+        let mut actions = column![
             button(Icon::Play.svg())
                 .on_press(Message::LinkMessage(
                     self.movie.id,
-                    LinkMessage::LinkToClipboard(details.cloned(), ShiftPressed::Unknown)
+                    LinkMessage::ActivateDefault(details.cloned())
                 ))
                 .width(Length::Fixed(ICON_SIZE * 2.)),
+            tooltip(
+                button(Icon::Copy.svg())
+                    .on_press(Message::LinkMessage(
+                        self.movie.id,
+                        LinkMessage::CopyDefault(details.cloned())
+                    ))
+                    .width(Length::Fixed(ICON_SIZE * 2.)),
+                "Copy without advancing",
+                tooltip::Position::Left
+            )
+            .style(theme::Container::Box),
+        ]
+        .spacing(10);
+        if draggable {
+            actions = actions.push(self.drag_handle());
+        }
+        if self.finished && !self.is_rewatching() {
+            actions = actions.push(
+                button(Icon::Rewatch.svg())
+                    .on_press(Message::BookmarkMessage(
+                        self.movie.id,
+                        BookmarkMessage::StartRewatch,
+                    ))
+                    .width(Length::Fixed(ICON_SIZE * 2.)),
+            );
+        }
+        if let Some(behind) = details
+            .map(|details| self.episodes_behind(details))
+            .filter(|behind| *behind > 0)
+        {
+            actions = actions.push(
+                container(text(format!("+{behind}")))
+                    .center_x()
+                    .width(Length::Fixed(ICON_SIZE * 2.))
+                    .padding(5)
+                    .style(theme::Container::Box),
+            );
+        }
+        if let Some(rating) = self.rating {
+            actions = actions.push(
+                tooltip(
+                    container(text(format!("{rating}/10")))
+                        .center_x()
+                        .width(Length::Fixed(ICON_SIZE * 2.))
+                        .padding(5)
+                        .style(theme::Container::Box),
+                    "Your rating",
+                    tooltip::Position::Left,
+                )
+                .style(theme::Container::Box),
+            );
+        }
+        let picture_row = row![
+            Space::with_width(Length::Fill),
+            picture_button(self.movie.id, poster, Length::FillPortion(3)),
+            actions,
         ];
-        let progress = text(format!("PROGRESS: {}", self.current_episode.as_info_str()))
-            .width(Length::Fill)
-            .horizontal_alignment(Horizontal::Center);
+        let progress = if let Some(rewatch) = &self.rewatch {
+            text(format!("REWATCH: {}", rewatch.as_info_str()))
+        } else {
+            text(format!("PROGRESS: {}", self.current_episode.as_info_str()))
+        }
+        .width(Length::Fill)
+        .horizontal_alignment(Horizontal::Center);
+        let progress = row![
+            step_button(self.movie.id, Icon::Minus, details, false),
+            progress,
+            step_button(self.movie.id, Icon::Plus, details, true),
+        ]
+        .align_items(Alignment::Center);
         let latest = if let Some(details) = &details {
             if let Some(last) = details.last_published() {
                 text(format!(
@@ -50,20 +131,144 @@ impl Bookmark {
         }
         .width(Length::Fill)
         .horizontal_alignment(Horizontal::Center);
+        let refreshed = text(match refreshed {
+            Some(refreshed) => format!("refreshed {}", format_elapsed(refreshed)),
+            None => "not refreshed yet".into(),
+        })
+        .size(14)
+        .width(Length::Fill)
+        .horizontal_alignment(Horizontal::Center)
+        .style(style.muted);
         column![
             picture_row,
-            text(self.movie.name.as_str())
-                .horizontal_alignment(Horizontal::Center)
-                .width(Length::Fill)
-                .size(FONT_SIZE_HEADER),
+            container(highlighted_text(
+                &self.movie.name,
+                highlights,
+                style.fonts.header,
+                style
+            ))
+            .center_x()
+            .width(Length::Fill),
+            self.tag_chips(),
             progress,
-            latest
+            latest,
+            refreshed
         ]
         .into()
     }
+    /// Tags of the bookmark, pressing one filters by it
+    fn tag_chips(&self) -> Element<'_, Message> {
+        let chips = self.tags.iter().map(|tag| {
+            button(text(tag).size(14))
+                .style(theme::Button::Secondary)
+                .on_press(Message::TagFilterToggled(tag.clone()))
+                .padding([2, 8])
+                .into()
+        });
+        container(row(chips).spacing(5))
+            .center_x()
+            .width(Length::Fill)
+            .into()
+    }
+}
+/// Small button moving the progress of the bookmark one episode forward or back
+fn step_button(
+    id: MovieId,
+    icon: Icon,
+    details: Option<&MovieDetails>,
+    forward: bool,
+) -> Element<'static, Message> {
+    let message = if forward {
+        BookmarkMessage::IncrE(details.cloned())
+    } else {
+        BookmarkMessage::DecrE(details.cloned())
+    };
+    button(icon.text_svg())
+        .style(theme::Button::Text)
+        .on_press(Message::BookmarkMessage(id, message))
+        .padding(4)
+        .into()
+}
+impl Bookmark {
+    /// Compact single row alternative to the card
+    pub fn list_view<'a>(
+        &'a self,
+        details: Option<&MovieDetails>,
+        draggable: bool,
+        highlights: &[usize],
+        style: ViewStyle,
+    ) -> Element<'a, Message> {
+        let id = self.movie.id;
+        let progress = match &self.rewatch {
+            Some(rewatch) => format!("REWATCH: {}", rewatch.as_info_str()),
+            None => self.current_episode.as_info_str(),
+        };
+        let latest = details
+            .and_then(|details| details.last_published())
+            .map(|last| format!("LATEST: {}", Episode::from(last.episode).as_info_str()))
+            .unwrap_or_default();
+        let behind = details
+            .map(|details| self.episodes_behind(details))
+            .filter(|behind| *behind > 0)
+            .map(|behind| format!("+{behind}"))
+            .unwrap_or_default();
+        let mut list_row = row![].spacing(10).align_items(Alignment::Center);
+        if draggable {
+            list_row = list_row.push(self.drag_handle());
+        }
+        list_row
+            .extend([
+                button(highlighted_text(
+                    &self.movie.name,
+                    highlights,
+                    style.fonts.text,
+                    style,
+                ))
+                .style(theme::Button::Text)
+                .on_press(Message::FilterChanged(Filter::Details(id)))
+                .width(Length::FillPortion(4))
+                .into(),
+                text(progress).width(Length::FillPortion(2)).into(),
+                text(latest)
+                    .style(style.muted)
+                    .width(Length::FillPortion(2))
+                    .into(),
+                text(behind).width(Length::Fixed(ICON_SIZE * 2.)).into(),
+                button(Icon::Play.svg())
+                    .on_press(Message::LinkMessage(
+                        id,
+                        LinkMessage::ActivateDefault(details.cloned()),
+                    ))
+                    .width(Length::Fixed(ICON_SIZE * 2.))
+                    .into(),
+                tooltip(
+                    button(Icon::Copy.svg())
+                        .on_press(Message::LinkMessage(
+                            id,
+                            LinkMessage::CopyDefault(details.cloned()),
+                        ))
+                        .width(Length::Fixed(ICON_SIZE * 2.)),
+                    "Copy without advancing",
+                    tooltip::Position::Left,
+                )
+                .style(theme::Container::Box)
+                .into(),
+            ])
+            .into()
+    }
+    /// Grip which starts dragging the bookmark when pressed
+    fn drag_handle(&self) -> Element<'_, Message> {
+        mouse_area(
+            container(Icon::Move.text_svg())
+                .center_x()
+                .width(Length::Fixed(ICON_SIZE * 2.)),
+        )
+        .on_press(Message::DragStart(self.movie.id))
+        .into()
+    }
 }
 impl TmdbMovie {
-    pub fn view<'a>(&'a self, poster: Option<&'a Poster>) -> Element<Message> {
+    pub fn view<'a>(&'a self, poster: Option<&'a Poster>) -> Element<'a, Message> {
         let info_column = column![
             text(self.name.as_str()).style(theme::Text::Default),
             text(format!("Rating: {0}%", self.rating())),
@@ -95,7 +300,48 @@ impl TmdbMovie {
         .into()
     }
 }
-fn picture_button(id: MovieId, poster: Option<&Poster>, width: Length) -> Element<Message> {
+/// Formats the time passed since `time` in a human readable way, e.g. `5 min ago`
+fn format_elapsed(time: SystemTime) -> String {
+    let Ok(elapsed) = time.elapsed() else {
+        return "just now".into();
+    };
+    let minutes = elapsed.as_secs() / 60;
+    match minutes {
+        0 => "just now".into(),
+        1..=59 => format!("{minutes} min ago"),
+        60..=1439 => format!("{} h ago", minutes / 60),
+        _ => format!("{} days ago", minutes / 1440),
+    }
+}
+/// Text with the characters at the indices in the highlight color
+fn highlighted_text(
+    content: &str,
+    highlights: &[usize],
+    size: u16,
+    style: ViewStyle,
+) -> Element<'static, Message> {
+    if highlights.is_empty() {
+        return text(content).size(size).into();
+    }
+    let mut segments: Vec<(bool, String)> = Vec::new();
+    for (index, c) in content.chars().enumerate() {
+        let highlighted = highlights.contains(&index);
+        match segments.last_mut() {
+            Some((last, segment)) if *last == highlighted => segment.push(c),
+            _ => segments.push((highlighted, c.to_string())),
+        }
+    }
+    row(segments.into_iter().map(|(highlighted, segment)| {
+        let segment = text(segment).size(size);
+        if highlighted {
+            segment.style(style.highlight).into()
+        } else {
+            segment.into()
+        }
+    }))
+    .into()
+}
+fn picture_button(id: MovieId, poster: Option<&Poster>, width: Length) -> Element<'_, Message> {
     if let Some(Poster::Image(img)) = &poster {
         button(Image::<image::Handle>::new(img.clone())).width(width)
     } else {
@@ -104,7 +350,7 @@ fn picture_button(id: MovieId, poster: Option<&Poster>, width: Length) -> Elemen
     .on_press(Message::FilterChanged(Filter::Details(id)))
     .into()
 }
-fn picture_view(poster: Option<&Poster>, width: Length) -> Element<Message> {
+fn picture_view(poster: Option<&Poster>, width: Length) -> Element<'_, Message> {
     if let Some(Poster::Image(img)) = &poster {
         container(Image::<image::Handle>::new(img.clone())).width(width)
     } else {
@@ -112,34 +358,111 @@ fn picture_view(poster: Option<&Poster>, width: Length) -> Element<Message> {
     }
     .into()
 }
-pub(crate) fn view_details(
-    movie: &TmdbMovie,
-    input_caches: &InputCaches,
+pub(crate) fn view_details<'a>(
+    bookmark: &'a Bookmark,
+    state: &'a State,
     details: Option<&MovieDetails>,
     poster: Option<&Poster>,
     current: Option<EpisodeDetails>,
-    sync: SyncMode,
-) -> Element<'static, Message> {
+) -> Element<'a, Message> {
+    let movie = &bookmark.movie;
+    let style = state.view_style();
     column![
         button(Icon::ArrowLeft.svg())
             .on_press(Message::FilterChanged(Filter::Bookmarks))
             .width(Length::Fixed(ICON_SIZE * 2.)),
-        text(format!("{} [{}]", &movie.name, &movie.original_name)).size(FONT_SIZE_HEADER),
-        details_view_info(details, poster, current.as_ref()),
-        details_view_edit(input_caches, movie.id, sync)
+        text(format!("{} [{}]", &movie.name, &movie.original_name)).size(style.fonts.header),
+        details_view_info(details, poster, current.as_ref(), style),
+        details_view_notes(state, bookmark),
+        details_view_edit(state, bookmark, details)
     ]
     .spacing(20)
     .into()
 }
+/// Personal rating, notes on the show and notes on single episodes
+fn details_view_notes<'a>(state: &'a State, bookmark: &'a Bookmark) -> Column<'a, Message> {
+    let style = state.view_style();
+    let id = bookmark.movie.id;
+    let ratings: Vec<u8> = (1..=10).collect();
+    let mut rating_row = row![
+        text("Rating ").size(style.fonts.text),
+        pick_list(ratings, bookmark.rating, move |rating| {
+            Message::BookmarkMessage(id, BookmarkMessage::SetRating(Some(rating)))
+        })
+        .placeholder("Not rated"),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    if bookmark.rating.is_some() {
+        rating_row = rating_row.push(
+            button(Icon::X.svg())
+                .on_press(Message::BookmarkMessage(
+                    id,
+                    BookmarkMessage::SetRating(None),
+                ))
+                .width(Length::Fixed(50.)),
+        );
+    }
+    let notes = text_editor(&state.gui.notes_editor)
+        .on_action(Message::NotesEdited)
+        .height(Length::Fixed(100.));
+    let current = bookmark.current_episode_id();
+    let current_note = state
+        .episode_notes
+        .get(&current)
+        .cloned()
+        .unwrap_or_default();
+    let episode_note_row = row![
+        text(format!("Note on {} ", current.1.as_info_str())).size(style.fonts.text),
+        text_input("e.g. stopped at 23:10", &current_note)
+            .on_input(move |note| Message::EpisodeNoteChanged(current.clone(), note))
+            .width(Length::Fill),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    let mut episode_notes: Vec<(&EpisodeId, &String)> = state
+        .episode_notes
+        .iter()
+        .filter(|(episode, _)| episode.0 == id && **episode != bookmark.current_episode_id())
+        .collect();
+    episode_notes.sort_by_key(|(episode, _)| (episode.1.season(), episode.1.episode()));
+    let episode_notes = episode_notes.into_iter().map(|(episode, note)| {
+        row![
+            text(episode.1.as_info_str()).width(Length::Fixed(120.)),
+            text(note).style(style.muted).width(Length::Fill),
+            button(Icon::Trash.svg())
+                .on_press(Message::EpisodeNoteChanged(episode.clone(), String::new()))
+                .width(Length::Fixed(50.)),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into()
+    });
+    column![
+        rating_row,
+        text("Notes").size(style.fonts.text),
+        notes,
+        episode_note_row,
+        column(episode_notes).spacing(5),
+    ]
+    .spacing(10)
+}
 fn details_view_edit(
-    input_caches: &InputCaches,
-    id: MovieId,
-    sync: SyncMode,
+    state: &State,
+    bookmark: &Bookmark,
+    details: Option<&MovieDetails>,
 ) -> Column<'static, Message> {
+    let input_caches = &state.gui.input_caches;
+    let link_action = state.gui.link_action;
+    let style = state.view_style();
+    let id = bookmark.movie.id;
+    let links = state.links.get(&id);
+    let library = state.libraries.get(&id);
+    let sync = bookmark.sync_mode;
     let episode = &input_caches[InputKind::EpisodeInput];
     let season = &input_caches[InputKind::SeasonInput];
     let current_progress_row = row![
-        text("Progress ").size(FONT_SIZE),
+        text("Progress ").size(style.fonts.text),
         column![
             text("Episode").line_height(LineHeight::Relative(2.)),
             text("Season").line_height(LineHeight::Relative(2.)),
@@ -166,7 +489,61 @@ fn details_view_edit(
         .on_press(Message::BookmarkMessage(id, BookmarkMessage::ToggleSync))
     ]
     .spacing(10);
+    let mut step_row = row![
+        text("Episodes ").size(style.fonts.text),
+        button(Icon::Minus.svg())
+            .on_press(Message::BookmarkMessage(
+                id,
+                BookmarkMessage::DecrE(details.cloned())
+            ))
+            .width(Length::Fixed(50.)),
+        button(Icon::Plus.svg())
+            .on_press(Message::BookmarkMessage(
+                id,
+                BookmarkMessage::IncrE(details.cloned())
+            ))
+            .width(Length::Fixed(50.)),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    let has_seasons = matches!(bookmark.active_episode(), Episode::Seasonal(_));
+    if sync == SyncMode::Tmdb || has_seasons {
+        step_row = step_row.push(
+            tooltip(
+                button(row![Icon::SkipForward.svg(), text("Finish season")].spacing(5)).on_press(
+                    Message::BookmarkMessage(id, BookmarkMessage::FinishSeason(details.cloned())),
+                ),
+                "Continue with the first episode of the next season",
+                tooltip::Position::Bottom,
+            )
+            .style(theme::Container::Box),
+        );
+    }
+    // A finished bookmark already watched the latest episode
+    if sync == SyncMode::Tmdb && !bookmark.is_rewatching() && !bookmark.finished {
+        if let Some(last) = details.and_then(|details| details.last_published()) {
+            step_row = step_row.push(
+                tooltip(
+                    button(row![Icon::FastForward.svg(), text("Jump to latest")].spacing(5))
+                        .on_press(Message::BookmarkMessage(
+                            id,
+                            BookmarkMessage::JumpToLatest(details.cloned()),
+                        )),
+                    text(format!(
+                        "Continue with the latest aired episode {}",
+                        Episode::from(last.episode).as_info_str()
+                    )),
+                    tooltip::Position::Bottom,
+                )
+                .style(theme::Container::Box),
+            );
+        }
+    }
     let link = &input_caches[InputKind::LinkInput];
+    let name_input = text_input("Name", &input_caches[InputKind::LinkNameInput])
+        .on_submit(Message::InputSubmit(InputKind::LinkNameInput))
+        .on_input(|input| Message::InputChanged(InputKind::LinkNameInput, input))
+        .width(Length::Fixed(120.));
     let link_input = text_input("https://link_to_movie/episode-{e}-season-{s}", link)
         .on_submit(Message::InputSubmit(InputKind::LinkInput))
         .on_input(|input| Message::InputChanged(InputKind::LinkInput, input))
@@ -174,21 +551,288 @@ fn details_view_edit(
     let enter = button(Icon::Check.svg())
         .on_press(Message::InputSubmit(InputKind::LinkInput))
         .width(Length::Fixed(50.));
-    let link_row = row![text("URL ").size(FONT_SIZE), link_input, enter].spacing(10);
+    let link_row = row![
+        text("URL ").size(style.fonts.text),
+        name_input,
+        link_input,
+        enter
+    ]
+    .spacing(10);
+    let mut action_row = row![
+        text("Action ").size(style.fonts.text),
+        pick_list(&LinkAction::KINDS[..], Some(link_action), move |kind| {
+            Message::LinkMessage(id, LinkMessage::ActionKindChanged(kind))
+        }),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    if link_action == LinkActionKind::Command {
+        action_row = action_row.push(
+            text_input("mpv --fs {url}", &input_caches[InputKind::LinkCommandInput])
+                .on_submit(Message::InputSubmit(InputKind::LinkCommandInput))
+                .on_input(|input| Message::InputChanged(InputKind::LinkCommandInput, input))
+                .width(Length::Fill),
+        );
+    }
+    let link_preview = link_preview(link, bookmark, details, style);
+    let link_list = details_view_links(bookmark, details, links, style);
 
     let remove_bookmark = row![
-        text("Delete Bookmark").size(FONT_SIZE),
+        text("Delete Bookmark").size(style.fonts.text),
         button(Icon::Trash.svg())
             .on_press(Message::RemoveBookmark(id))
             .width(Length::Fixed(50.))
     ]
     .spacing(10);
-    column![current_progress_row, link_row, remove_bookmark].spacing(10)
+    let tags = bookmark.tags.iter().map(|tag| {
+        button(text(format!("{tag} ×")))
+            .style(theme::Button::Secondary)
+            .on_press(Message::BookmarkMessage(
+                id,
+                BookmarkMessage::RemoveTag(tag.clone()),
+            ))
+            .padding([4, 10])
+            .into()
+    });
+    let tag_row = row![text("Tags ").size(style.fonts.text)]
+        .extend(tags)
+        .push(
+            text_input("Add tag", &input_caches[InputKind::TagInput])
+                .on_submit(Message::InputSubmit(InputKind::TagInput))
+                .on_input(|input| Message::InputChanged(InputKind::TagInput, input))
+                .width(Length::Fixed(160.)),
+        )
+        .push(
+            button(Icon::Plus.svg())
+                .on_press(Message::InputSubmit(InputKind::TagInput))
+                .width(Length::Fixed(50.)),
+        )
+        .spacing(10)
+        .align_items(Alignment::Center);
+    let status_row = row![
+        text("Status ").size(style.fonts.text),
+        pick_list(
+            &WatchStatus::ALL[..],
+            Some(bookmark.status()),
+            move |status| { Message::BookmarkMessage(id, BookmarkMessage::SetStatus(status)) }
+        ),
+        if bookmark.muted {
+            button(Icon::BellOff.svg())
+        } else {
+            button(Icon::Bell.svg())
+        }
+        .width(Length::Fixed(50.))
+        .on_press(Message::BookmarkMessage(id, BookmarkMessage::ToggleMute))
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    column![
+        current_progress_row,
+        step_row,
+        status_row,
+        tag_row,
+        details_view_rewatch(bookmark, style),
+        link_list,
+        link_row,
+        action_row,
+        link_preview,
+        details_view_library(input_caches, bookmark, details, library, style),
+        remove_bookmark
+    ]
+    .spacing(10)
+}
+/// Directory of the episodes on disk and which episodes were found in it
+fn details_view_library(
+    input_caches: &InputCaches,
+    bookmark: &Bookmark,
+    details: Option<&MovieDetails>,
+    library: Option<&Result<LocalLibrary, String>>,
+    style: ViewStyle,
+) -> Column<'static, Message> {
+    let id = bookmark.movie.id;
+    let dir_row = row![
+        text("Folder ").size(style.fonts.text),
+        text_input("/path/to/episodes", &input_caches[InputKind::LocalDirInput])
+            .on_submit(Message::InputSubmit(InputKind::LocalDirInput))
+            .on_input(|input| Message::InputChanged(InputKind::LocalDirInput, input))
+            .width(Length::Fill),
+        button(Icon::Check.svg())
+            .on_press(Message::InputSubmit(InputKind::LocalDirInput))
+            .width(Length::Fixed(50.)),
+        button(Icon::Refresh.svg())
+            .on_press_maybe(
+                bookmark
+                    .local_dir
+                    .is_some()
+                    .then_some(Message::ScanLibrary(id))
+            )
+            .width(Length::Fixed(50.)),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    let mut library_column = column![dir_row].spacing(5);
+    match library {
+        None => {}
+        Some(Err(e)) => library_column = library_column.push(text(e).style(style.error)),
+        Some(Ok(library)) => {
+            let available = library.available(details);
+            if available.is_empty() {
+                library_column = library_column.push(text("No episodes found").style(style.muted));
+            }
+            let next = if library.find(bookmark.active_episode(), details).is_some() {
+                "The next episode is available locally"
+            } else {
+                "The next episode isn't available locally"
+            };
+            library_column = library_column.push(text(next).style(style.muted));
+            for season in available.chunk_by(|a, b| a.season_number == b.season_number) {
+                let episodes: Vec<usize> = season.iter().map(|e| e.episode_number).collect();
+                library_column = library_column.push(
+                    row![
+                        Icon::Folder.text_svg(),
+                        text(format!(
+                            "Season {}: {}",
+                            season[0].season_number,
+                            format_ranges(&episodes)
+                        ))
+                    ]
+                    .spacing(10),
+                );
+            }
+        }
+    }
+    library_column
+}
+/// Formats sorted numbers as ranges, e.g. `1-3, 5`
+fn format_ranges(numbers: &[usize]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut start = 0;
+    for i in 1..=numbers.len() {
+        if i == numbers.len() || numbers[i] != numbers[i - 1] + 1 {
+            ranges.push(if i - 1 == start {
+                numbers[start].to_string()
+            } else {
+                format!("{}-{}", numbers[start], numbers[i - 1])
+            });
+            start = i;
+        }
+    }
+    ranges.join(", ")
+}
+/// Lists the links of the bookmark with buttons to use, edit, remove or make them the default
+fn details_view_links(
+    bookmark: &Bookmark,
+    details: Option<&MovieDetails>,
+    links: Option<&BookmarkLinks>,
+    style: ViewStyle,
+) -> Column<'static, Message> {
+    let id = bookmark.movie.id;
+    let Some(links) = links else {
+        return Column::new();
+    };
+    let default = links.default_index();
+    column(links.links.iter().enumerate().map(|(index, link)| {
+        row![
+            button(if index == default {
+                Icon::Star.text_svg()
+            } else {
+                Icon::Circle.text_svg()
+            })
+            .style(theme::Button::Text)
+            .on_press(Message::LinkMessage(id, LinkMessage::SetDefault(index)))
+            .width(Length::Fixed(50.)),
+            text(link.name.clone()).width(Length::Fixed(120.)),
+            text(link.link.string_link.clone())
+                .style(style.muted)
+                .width(Length::Fill),
+            text(link.action.kind().to_string())
+                .style(style.muted)
+                .width(Length::Fixed(120.)),
+            button(match link.action {
+                LinkAction::Copy => Icon::Copy.svg(),
+                _ => Icon::Play.svg(),
+            })
+            .on_press(Message::LinkMessage(
+                id,
+                LinkMessage::Activate(index, details.cloned())
+            ))
+            .width(Length::Fixed(50.)),
+            button(Icon::Edit.svg())
+                .on_press(Message::LinkMessage(id, LinkMessage::Edit(index)))
+                .width(Length::Fixed(50.)),
+            button(Icon::Trash.svg())
+                .on_press(Message::LinkMessage(id, LinkMessage::Remove(index)))
+                .width(Length::Fixed(50.)),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into()
+    }))
+    .spacing(5)
+}
+/// Shows the url the link template resolves to for the current episode,
+/// or marks the position of the error in the template
+fn link_preview(
+    link: &str,
+    bookmark: &Bookmark,
+    details: Option<&MovieDetails>,
+    style: ViewStyle,
+) -> Element<'static, Message> {
+    if link.is_empty() {
+        return Space::with_height(Length::Shrink).into();
+    }
+    match Link::new(link) {
+        Ok(template) => {
+            let values = LinkValues::new(&bookmark.movie.name, bookmark.active_episode(), details);
+            let url = template
+                .url(&values)
+                .unwrap_or_else(|| "details are needed to preview the link".into());
+            text(url).style(style.muted).into()
+        }
+        Err(e) => {
+            let position = e.position().unwrap_or(link.len()).min(link.len());
+            column![
+                row![
+                    text(link[..position].to_owned()).style(style.muted),
+                    text(link[position..].to_owned()).style(style.error),
+                ],
+                text(e.to_string()).style(style.error)
+            ]
+            .into()
+        }
+    }
+}
+fn details_view_rewatch(bookmark: &Bookmark, style: ViewStyle) -> Row<'static, Message> {
+    let id = bookmark.movie.id;
+    let count = match bookmark.rewatch_count {
+        0 => String::new(),
+        1 => " (rewatched once)".into(),
+        n => format!(" (rewatched {n} times)"),
+    };
+    if let Some(rewatch) = &bookmark.rewatch {
+        row![
+            text(format!("Rewatching at {}{count}", rewatch.as_info_str())).size(style.fonts.text),
+            button(Icon::X.svg())
+                .on_press(Message::BookmarkMessage(id, BookmarkMessage::StopRewatch))
+                .width(Length::Fixed(50.))
+        ]
+    } else if bookmark.finished {
+        row![
+            text(format!("Start Rewatch{count}")).size(style.fonts.text),
+            button(Icon::Rewatch.svg())
+                .on_press(Message::BookmarkMessage(id, BookmarkMessage::StartRewatch))
+                .width(Length::Fixed(50.))
+        ]
+    } else {
+        row![text(count).size(style.fonts.text)]
+    }
+    .spacing(10)
 }
 fn details_view_info(
     details: Option<&MovieDetails>,
     poster: Option<&Poster>,
     current: Option<&EpisodeDetails>,
+    style: ViewStyle,
 ) -> Row<'static, Message> {
     let mut poster_row = Row::new();
     if let Some(poster) = poster {
@@ -200,7 +844,7 @@ fn details_view_info(
     let latest_episode_block: Element<_, _, _> = if let Some(details) = details {
         let latest = details.last_published().unwrap();
         row![
-            container(text("Latest Episode: ").size(FONT_SIZE)).center_y(),
+            container(text("Latest Episode: ").size(style.fonts.text)).center_y(),
             column![text(&latest.name), text(latest.episode.as_info_str())]
         ]
         .into()
@@ -210,13 +854,16 @@ fn details_view_info(
     let upcoming_episode_block: Element<_, _, _> = if let Some(details) = details {
         if let Some(upcoming_episode) = details.next_episode_to_air() {
             row![
-                text("Upcoming Episode: ").size(FONT_SIZE),
+                text("Upcoming Episode: ").size(style.fonts.text),
                 column![
                     text(&upcoming_episode.name),
                     text(upcoming_episode.episode.as_info_str()),
                     text(format!(
                         "Releases at {}",
-                        upcoming_episode.air_date.unwrap_or("---".into())
+                        upcoming_episode
+                            .air_date
+                            .map(|date| date.to_string())
+                            .unwrap_or("---".into())
                     ))
                 ]
             ]
@@ -229,7 +876,7 @@ fn details_view_info(
     };
     let details_block = column![
         row![
-            text("Current Episode: ").size(FONT_SIZE),
+            text("Current Episode: ").size(style.fonts.text),
             if let Some(current) = current {
                 column![text(&current.name), text(current.episode.as_info_str())]
             } else {
@@ -244,3 +891,475 @@ fn details_view_info(
     poster_row = poster_row.push(details_block);
     poster_row
 }
+/// Lists the shortcuts of the keymap
+pub(crate) fn view_keymap(state: &State) -> Element<'_, Message> {
+    let style = state.view_style();
+    let header = row![
+        text("Shortcuts")
+            .size(style.fonts.header)
+            .width(Length::Fill),
+        button(Icon::X.svg())
+            .on_press(Message::ToggleKeymap)
+            .width(Length::Fixed(ICON_SIZE * 2.)),
+    ]
+    .align_items(Alignment::Center);
+    let bindings = state.keymap.bindings().iter().map(|(action, bindings)| {
+        let keys = if bindings.is_empty() {
+            "unbound".to_owned()
+        } else {
+            bindings
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        row![
+            text(action.to_string()).width(Length::FillPortion(2)),
+            text(keys).style(style.muted).width(Length::FillPortion(1)),
+        ]
+        .spacing(10)
+        .into()
+    });
+    column![
+        header,
+        column(bindings).spacing(8),
+        text(format!("Rebind them in {}", keymap_path().display())).style(style.muted),
+    ]
+    .spacing(20)
+    .into()
+}
+/// Formats minutes like `3 h 20 min`
+fn format_duration(minutes: usize) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes} min"),
+        (hours, 0) => format!("{hours} h"),
+        (hours, minutes) => format!("{hours} h {minutes} min"),
+    }
+}
+pub(crate) fn view_stats(state: &State) -> Element<'_, Message> {
+    let style = state.view_style();
+    let stats = state.stats();
+    let range = state.gui.stats_range;
+    let range_button = |target: StatsRange| {
+        button(text(target.to_string()))
+            .style(if range == target {
+                theme::Button::Primary
+            } else {
+                theme::Button::Text
+            })
+            .on_press(Message::StatsRangeChanged(target))
+            .padding(8)
+    };
+    let export_row = row![
+        text(state.gui.stats_export.clone().unwrap_or_default())
+            .style(style.muted)
+            .width(Length::Fill),
+        button(text("Export JSON"))
+            .on_press(Message::ExportStats)
+            .padding(8),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    if stats.episodes_watched == 0 {
+        return column![
+            empty_message(Filter::Stats.empty_message(), style),
+            export_row
+        ]
+        .spacing(20)
+        .into();
+    }
+    let figure = |label: &str, value: String| {
+        column![
+            text(value).size(style.fonts.header),
+            text(label).style(style.muted),
+        ]
+        .spacing(5)
+        .width(Length::Fill)
+        .align_items(Alignment::Center)
+    };
+    let mut watch_time = format_duration(stats.watch_time_minutes);
+    if stats.episodes_without_runtime > 0 {
+        watch_time.push_str(" +");
+    }
+    let completion_rate = stats
+        .completion_rate
+        .map(|rate| format!("{:.0}%", rate * 100.))
+        .unwrap_or_else(|| "-".to_owned());
+    let figures = row![
+        figure("Episodes watched", stats.episodes_watched.to_string()),
+        figure("Watch time", watch_time),
+        figure("Completed", completion_rate),
+        figure(
+            "Longest streak",
+            format!("{} days", stats.longest_streak_days)
+        ),
+        figure(
+            "Current streak",
+            format!("{} days", stats.current_streak_days)
+        ),
+        figure(
+            "Backlog",
+            format!(
+                "{} episodes in {} shows",
+                stats.backlog_episodes, stats.backlog_shows
+            )
+        ),
+    ]
+    .spacing(10);
+    let (periods, date_format) = match range {
+        StatsRange::Weeks => (&stats.episodes_per_week, "%d %b"),
+        StatsRange::Months => (&stats.episodes_per_month, "%b"),
+    };
+    let chart = BarChart {
+        bars: periods
+            .iter()
+            .map(|period| {
+                (
+                    period.start.format(date_format).to_string(),
+                    period.episodes,
+                )
+            })
+            .collect(),
+    };
+    let chart_header = row![
+        text("Episodes per period")
+            .size(style.fonts.text)
+            .width(Length::Fill),
+        range_button(StatsRange::Weeks),
+        range_button(StatsRange::Months),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    let most_watched = stats.genres.first().map_or(1, |(_, count)| *count);
+    let genres = stats.genres.iter().take(8).map(|(name, count)| {
+        row![
+            text(name).width(Length::FillPortion(1)),
+            progress_bar(0.0..=most_watched as f32, *count as f32)
+                .height(Length::Fixed(12.))
+                .width(Length::FillPortion(3)),
+            text(count).width(Length::Fixed(50.)),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into()
+    });
+    column![
+        figures,
+        chart_header,
+        canvas(chart)
+            .width(Length::Fill)
+            .height(Length::Fixed(250.)),
+        text("Most watched genres").size(style.fonts.text),
+        column(genres).spacing(8),
+        export_row,
+    ]
+    .spacing(20)
+    .into()
+}
+pub(crate) fn view_calendar(state: &State) -> Element<'_, Message> {
+    let style = state.view_style();
+    let range = state.gui.calendar_range;
+    let range_button = |label, target: CalendarRange| {
+        button(text(label))
+            .style(if range == target {
+                theme::Button::Primary
+            } else {
+                theme::Button::Text
+            })
+            .on_press(Message::CalendarRangeChanged(target))
+            .padding(8)
+    };
+    let range_row = row![
+        Space::with_width(Length::Fill),
+        range_button("Week", CalendarRange::Week),
+        range_button("Month", CalendarRange::Month),
+    ]
+    .spacing(10);
+    let calendar_path = &state.gui.input_caches[InputKind::CalendarPath];
+    let export_row = row![
+        text_input("Keep calendar file updated at path", calendar_path)
+            .on_input(|input| Message::InputChanged(InputKind::CalendarPath, input))
+            .on_submit(Message::InputSubmit(InputKind::CalendarPath))
+            .width(Length::Fill),
+        button(Icon::Check.svg())
+            .on_press(Message::InputSubmit(InputKind::CalendarPath))
+            .width(Length::Fixed(50.)),
+        button(text("Export .ics"))
+            .on_press(Message::ExportCalendar)
+            .padding(8),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    let export_info =
+        text(state.gui.calendar_export.clone().unwrap_or_default()).style(style.muted);
+    let today = calendar::today();
+    let groups = calendar::group_episodes(state.upcoming_episodes(), range, today);
+    if groups.is_empty() {
+        return column![
+            range_row,
+            empty_message(Filter::Calendar.empty_message(), style),
+            export_row,
+            export_info
+        ]
+        .spacing(20)
+        .into();
+    }
+    let groups = groups.into_iter().map(|group| {
+        let color = if group.past_due {
+            style.error
+        } else {
+            style.muted
+        };
+        let episodes = group.episodes.into_iter().map(|upcoming| {
+            let id = upcoming.bookmark.movie.id;
+            row![
+                button(text(upcoming.bookmark.movie.name.clone()))
+                    .style(theme::Button::Text)
+                    .on_press(Message::FilterChanged(Filter::Details(id)))
+                    .width(Length::FillPortion(3)),
+                text(format!(
+                    "{} {}",
+                    upcoming.episode.episode.as_info_str(),
+                    upcoming.episode.name
+                ))
+                .width(Length::FillPortion(3)),
+                text(calendar::relative_date(upcoming.air_date, today))
+                    .style(color)
+                    .width(Length::FillPortion(1)),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .into()
+        });
+        column![
+            text(group.title).size(style.fonts.text).style(color),
+            column(episodes).spacing(5)
+        ]
+        .spacing(10)
+        .into()
+    });
+    column![
+        range_row,
+        column(groups).spacing(30),
+        export_row,
+        export_info
+    ]
+    .spacing(20)
+    .into()
+}
+pub(crate) fn view_notifications(state: &State) -> Element<'_, Message> {
+    let style = state.view_style();
+    let quiet_hours = state.config.quiet_hours;
+    let hours: Vec<u32> = (0..24).collect();
+    let mut quiet_row = row![checkbox("Quiet hours", quiet_hours.is_some())
+        .on_toggle(|enabled| { Message::QuietHoursChanged(enabled.then(QuietHours::default)) })]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    if let Some(quiet_hours) = quiet_hours {
+        quiet_row = quiet_row
+            .push(text("from"))
+            .push(pick_list(
+                hours.clone(),
+                Some(quiet_hours.start),
+                move |start| {
+                    Message::QuietHoursChanged(Some(QuietHours {
+                        start,
+                        ..quiet_hours
+                    }))
+                },
+            ))
+            .push(text("until"))
+            .push(pick_list(hours, Some(quiet_hours.end), move |end| {
+                Message::QuietHoursChanged(Some(QuietHours { end, ..quiet_hours }))
+            }));
+    }
+    quiet_row = quiet_row.push(Space::with_width(Length::Fill)).push(
+        button(text("Clear history"))
+            .on_press(Message::ClearNotifications)
+            .padding(8),
+    );
+    if state.notifications.is_empty() {
+        return column![
+            quiet_row,
+            empty_message(Filter::Notifications.empty_message(), style)
+        ]
+        .spacing(20)
+        .into();
+    }
+    let history = state.notifications.iter().map(|notification| {
+        let time = notification.time.with_timezone(&Local);
+        let mut info = time.format("%d %B %H:%M").to_string();
+        if !notification.delivered {
+            info.push_str(" · held back");
+        }
+        column![
+            text(&notification.summary).size(style.fonts.text),
+            text(&notification.body),
+            text(info).style(style.muted),
+        ]
+        .spacing(5)
+        .into()
+    });
+    column![quiet_row, column(history).spacing(20)]
+        .spacing(20)
+        .into()
+}
+/// Deleted bookmarks which can be restored or purged
+pub(crate) fn view_archive(state: &State) -> Element<'_, Message> {
+    let style = state.view_style();
+    let mut purge_row = row![].spacing(10).align_items(Alignment::Center);
+    if let Some(info) = &state.gui.purge_info {
+        purge_row = purge_row.push(text(info).style(style.muted));
+    }
+    purge_row = purge_row.push(Space::with_width(Length::Fill)).push(
+        button(text("Purge all"))
+            .style(theme::Button::Destructive)
+            .on_press_maybe((!state.archive.is_empty()).then_some(Message::PurgeArchive))
+            .padding(8),
+    );
+    if state.archive.is_empty() {
+        return column![
+            purge_row,
+            empty_message(Filter::Archived.empty_message(), style)
+        ]
+        .spacing(20)
+        .into();
+    }
+    let archived = state.archive.iter().rev().map(|archived| {
+        let bookmark = &archived.bookmark;
+        let id = bookmark.movie.id;
+        let deleted = archived.archived.with_timezone(&Local);
+        row![
+            column![
+                text(&bookmark.movie.name).size(style.fonts.text),
+                text(format!(
+                    "{} · deleted {}",
+                    bookmark.current_episode.as_info_str(),
+                    deleted.format("%d %B %Y")
+                ))
+                .style(style.muted),
+            ]
+            .spacing(5)
+            .width(Length::Fill),
+            tooltip(
+                button(Icon::Rewatch.svg())
+                    .on_press(Message::RestoreBookmark(id))
+                    .width(Length::Fixed(ICON_SIZE * 2.)),
+                "Restore",
+                tooltip::Position::Left,
+            )
+            .style(theme::Container::Box),
+            tooltip(
+                button(Icon::Trash.svg())
+                    .style(theme::Button::Destructive)
+                    .on_press(Message::PurgeBookmark(id))
+                    .width(Length::Fixed(ICON_SIZE * 2.)),
+                "Delete permanently",
+                tooltip::Position::Left,
+            )
+            .style(theme::Container::Box),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into()
+    });
+    column![purge_row, column(archived).spacing(20)]
+        .spacing(20)
+        .into()
+}
+/// Edits the config, changes are applied right away
+pub(crate) fn view_settings(state: &State) -> Element<'_, Message> {
+    let style = state.view_style();
+    let label = |label| {
+        text(label)
+            .size(style.fonts.text)
+            .width(Length::Fixed(320.))
+    };
+    let inputs = Setting::ALL.iter().map(|setting| {
+        let input = state
+            .gui
+            .setting_inputs
+            .get(setting)
+            .cloned()
+            .unwrap_or_else(|| setting.get(&state.config));
+        let setting = *setting;
+        let mut entry = column![row![
+            label(setting.label()),
+            text_input("", &input)
+                .on_input(move |input| Message::SettingChanged(setting, input))
+                .width(Length::Fill),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center)];
+        if let Some(error) = state.gui.setting_errors.get(&setting) {
+            entry = entry.push(text(error).style(style.error));
+        }
+        entry.spacing(5).into()
+    });
+    let advance_row = row![
+        label("After playing"),
+        pick_list(
+            &AdvanceMode::ALL[..],
+            Some(state.config.advance_on_play),
+            Message::AdvanceModeChanged
+        ),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    let track_row = row![
+        label("Follow mpv playback"),
+        checkbox("", state.config.track_player_progress)
+            .on_toggle(Message::TrackPlayerProgressToggled),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    let theme_row = row![
+        label("Theme"),
+        pick_list(
+            state.theme_choices(),
+            Some(state.config.theme.clone()),
+            Message::ThemeChanged
+        ),
+        button(Icon::Refresh.svg())
+            .on_press(Message::ReloadThemes)
+            .width(Length::Fixed(50.)),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    let themes_info = text(format!(
+        "Custom palettes are read from {}",
+        themes_path().display()
+    ))
+    .style(style.muted);
+    let keymap_row = row![
+        label("Shortcuts"),
+        button(text("Show"))
+            .on_press(Message::ToggleKeymap)
+            .padding(8),
+        button(Icon::Refresh.svg())
+            .on_press(Message::ReloadKeymap)
+            .width(Length::Fixed(50.)),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    let keymap_info = match &state.keymap_error {
+        Some(e) => text(e).style(style.error),
+        None => text(format!(
+            "Shortcuts are rebound in {}",
+            keymap_path().display()
+        ))
+        .style(style.muted),
+    };
+    column![
+        column(inputs).spacing(10),
+        theme_row,
+        themes_info,
+        keymap_row,
+        keymap_info,
+        advance_row,
+        track_row,
+        text(format!("Saved to {}", save::config_path().display())).style(style.muted),
+    ]
+    .spacing(10)
+    .into()
+}